@@ -0,0 +1,281 @@
+// Copyright (c) 2020 Karl Thorén <karl.h.thoren@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Automatic brightness following the ambient light.
+
+use ht16k33::Dimming;
+
+use crate::Brightness;
+
+/// Default mapping from ambient light in lux to brightness.
+/// Dim at night, readable in an office and full brightness in daylight.
+const DEFAULT_CURVE: &[(f32, Brightness)] = &[
+    (0.0, Brightness::new(0)),
+    (50.0, Brightness::new(110)),
+    (400.0, Brightness::new(180)),
+    (10_000.0, Brightness::new(240)),
+    (30_000.0, Brightness::new(255)),
+];
+
+/// Controller mapping ambient light readings to dimming levels.
+///
+/// The readings can come from any light sensor, in lux. They are smoothed with an
+/// exponential moving average and mapped to a brightness through a curve of
+/// `(lux, brightness)` points, interpolating linearly between them.
+/// The dimming level only changes when the brightness has moved past the boundary to
+/// the next level by more than the hysteresis, so a reading near a boundary does not flicker.
+///
+/// # Examples
+///
+/// ```
+/// use ht16k33::i2c_mock::I2cMock;
+/// use ht16k33::HT16K33;
+/// use adafruit_7segment::{AutoBrightness, Brightness};
+///
+/// // Create an I2C device.
+/// let mut i2c = I2cMock::new();
+///
+/// // The I2C device address.
+/// const DISP_I2C_ADDR: u8 = 112;
+///
+/// let mut ht16k33 = HT16K33::new(i2c, DISP_I2C_ADDR);
+///
+/// let curve = [
+///     (0.0, Brightness::new(0)),
+///     (1000.0, Brightness::new(255)),
+/// ];
+/// let mut auto = AutoBrightness::new().curve(&curve).smoothing(0.5).hysteresis(10);
+///
+/// // Call for every reading of the light sensor.
+/// let lux = 320.0;
+/// if let Some(dimming) = auto.update(lux) {
+///     ht16k33.set_dimming(dimming).expect("Could not set dimming!");
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AutoBrightness<'a> {
+    curve: &'a [(f32, Brightness)],
+    smoothing: f32,
+    hysteresis: u8,
+    // Smoothed reading.
+    lux: Option<f32>,
+    // The dimming level last returned.
+    dimming: Option<Dimming>,
+}
+
+impl Default for AutoBrightness<'_> {
+    fn default() -> Self {
+        AutoBrightness {
+            curve: DEFAULT_CURVE,
+            smoothing: 0.25,
+            hysteresis: 8,
+            lux: None,
+            dimming: None,
+        }
+    }
+}
+
+impl<'a> AutoBrightness<'a> {
+    /// Create a controller with the default curve, smoothing of 0.25 and hysteresis of 8.
+    pub fn new() -> Self {
+        AutoBrightness::default()
+    }
+
+    /// Map the light through `curve`, `(lux, brightness)` points in increasing lux.
+    /// Light below the first or above the last point gives the brightness of that point.
+    pub fn curve<'b>(self, curve: &'b [(f32, Brightness)]) -> AutoBrightness<'b> {
+        AutoBrightness {
+            curve,
+            smoothing: self.smoothing,
+            hysteresis: self.hysteresis,
+            lux: self.lux,
+            dimming: self.dimming,
+        }
+    }
+
+    /// Weight of each new reading, from 0 to 1. 1 disables the smoothing,
+    /// lower values follow changes of the light more slowly.
+    pub fn smoothing(mut self, weight: f32) -> Self {
+        self.smoothing = weight.clamp(0.0, 1.0);
+        self
+    }
+
+    /// How far, in brightness from 0 to 255, the brightness must move past the boundary
+    /// to another dimming level before it is used.
+    pub fn hysteresis(mut self, hysteresis: u8) -> Self {
+        self.hysteresis = hysteresis;
+        self
+    }
+
+    /// The smoothed light in lux, `None` before the first reading.
+    pub fn lux(&self) -> Option<f32> {
+        self.lux
+    }
+
+    /// The current dimming level, `None` before the first reading.
+    pub fn dimming(&self) -> Option<Dimming> {
+        self.dimming
+    }
+
+    /// The brightness for the light according to the curve.
+    pub fn brightness(&self, lux: f32) -> Brightness {
+        let (first, last) = match (self.curve.first(), self.curve.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Brightness::new(u8::MAX),
+        };
+        if lux <= first.0 {
+            return first.1;
+        }
+        for pair in self.curve.windows(2) {
+            let ((low_lux, low), (high_lux, high)) = (pair[0], pair[1]);
+            if lux < high_lux {
+                let low = f32::from(low.level());
+                let high = f32::from(high.level());
+                let level = low + (high - low) * (lux - low_lux) / (high_lux - low_lux);
+                return Brightness::new((level + 0.5) as u8);
+            }
+        }
+        last.1
+    }
+
+    /// Whether the brightness is far enough past the boundary between the current
+    /// dimming level and its neighbour towards `target` to change level.
+    /// Reaching the brightness of the neighbour level is always far enough.
+    fn past_boundary(&self, current: Dimming, target: Dimming, level: u8) -> bool {
+        let step = if target > current { 1 } else { -1 };
+        let neighbour = Dimming::from_bits_truncate((current.bits() as i8 + step) as u8);
+        let current = i16::from(Brightness::from_dimming(current).level());
+        let neighbour = i16::from(Brightness::from_dimming(neighbour).level());
+        let boundary = (current + neighbour) / 2 + step as i16 * i16::from(self.hysteresis);
+        let level = i16::from(level);
+        if step > 0 {
+            level >= boundary.min(neighbour)
+        } else {
+            level <= boundary.max(neighbour)
+        }
+    }
+
+    /// Add a light reading.
+    /// Returns the dimming level to set, `None` if it is the same as last time.
+    /// Readings that are not a number or infinite are ignored, negative readings count as dark.
+    ///
+    /// # Arguments
+    ///
+    /// * `lux` - The ambient light in lux.
+    pub fn update(&mut self, lux: f32) -> Option<Dimming> {
+        if !lux.is_finite() {
+            return None;
+        }
+        let lux = lux.max(0.0);
+        let smoothed = match self.lux {
+            Some(previous) => previous + self.smoothing * (lux - previous),
+            None => lux,
+        };
+        self.lux = Some(smoothed);
+
+        let level = self.brightness(smoothed).level();
+        let dimming = Brightness::new(level).dimming();
+        if let Some(current) = self.dimming {
+            if dimming == current || !self.past_boundary(current, dimming, level) {
+                return None;
+            }
+        }
+        self.dimming = Some(dimming);
+        Some(dimming)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use self::std::vec::Vec;
+    use super::*;
+    use core::iter;
+
+    /// Feed a light trace, returning the dimming level after each reading.
+    fn run(auto: &mut AutoBrightness, trace: impl IntoIterator<Item = f32>) -> Vec<u8> {
+        trace
+            .into_iter()
+            .map(|lux| {
+                auto.update(lux);
+                auto.dimming().map_or(0xFF, |dimming| dimming.bits())
+            })
+            .collect()
+    }
+
+    /// Number of times the level changes during a run.
+    fn changes(levels: &[u8]) -> usize {
+        levels.windows(2).filter(|pair| pair[0] != pair[1]).count()
+    }
+
+    #[test]
+    fn curve() {
+        let auto = AutoBrightness::new();
+        assert_eq!(auto.brightness(-5.0), Brightness::new(0));
+        assert_eq!(auto.brightness(25.0), Brightness::new(55));
+        assert_eq!(auto.brightness(400.0), Brightness::new(180));
+        assert_eq!(auto.brightness(100_000.0), Brightness::new(255));
+        assert_eq!(auto.curve(&[]).brightness(0.0), Brightness::new(255));
+    }
+
+    #[test]
+    fn sunrise() {
+        // From night to full sunlight over 200 readings.
+        let trace = (0..200).map(|i| (i * i) as f32);
+        let levels = run(&mut AutoBrightness::new(), trace);
+        assert_eq!(levels[0], Dimming::BRIGHTNESS_MIN.bits());
+        assert_eq!(*levels.last().unwrap(), Dimming::BRIGHTNESS_MAX.bits());
+        assert!(levels.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn noise_at_boundary() {
+        // An office light flickering around a level boundary.
+        let mut auto = AutoBrightness::new().smoothing(1.0);
+        let trace = (0..100).map(|i| if i % 2 == 0 { 390.0 } else { 1040.0 });
+        assert_eq!(changes(&run(&mut auto, trace.clone())), 0);
+
+        // Without hysteresis the same light switches level on every reading.
+        let mut auto = AutoBrightness::new().smoothing(1.0).hysteresis(0);
+        assert_eq!(changes(&run(&mut auto, trace)), 99);
+    }
+
+    #[test]
+    fn passing_shadow() {
+        // Daylight, a shadow for 3 readings, then daylight again.
+        let trace = [10_000.0, 10_000.0, 50.0, 50.0, 50.0, 10_000.0, 10_000.0];
+        let sharp = run(&mut AutoBrightness::new().smoothing(1.0), trace);
+        let smooth = run(&mut AutoBrightness::new(), trace);
+        assert_eq!(sharp[..5], [13, 13, 2, 2, 2]);
+        // The smoothed level dips less and recovers.
+        assert!(smooth.iter().all(|&level| level > 2));
+        assert!(smooth[4] < smooth[0]);
+    }
+
+    #[test]
+    fn invalid_readings() {
+        let mut auto = AutoBrightness::new();
+        assert_eq!(auto.update(f32::NAN), None);
+        assert_eq!(auto.dimming(), None);
+        assert_eq!(auto.update(-1.0), Some(Dimming::BRIGHTNESS_MIN));
+        assert_eq!(auto.lux(), Some(0.0));
+        assert_eq!(auto.update(f32::NAN), None);
+        assert_eq!(auto.update(f32::INFINITY), None);
+        assert_eq!(auto.update(f32::NEG_INFINITY), None);
+        assert_eq!(auto.lux(), Some(0.0));
+
+        // A bad reading in daylight does not keep the display bright once it gets dark.
+        let trace = iter::repeat_n(10_000.0, 20)
+            .chain(iter::once(f32::INFINITY))
+            .chain(iter::repeat_n(0.0, 40));
+        let levels = run(&mut auto, trace);
+        assert_eq!(levels[19], 13);
+        assert_eq!(levels[20], 13);
+        assert_eq!(*levels.last().unwrap(), Dimming::BRIGHTNESS_MIN.bits());
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright (c) 2020 Karl Thorén <karl.h.thoren@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Horizontal bar graph drawn with the segments of the display.
+
+use crate::{Index, SevenSegment, DOT_BIT};
+
+/// Segments E and F, the left vertical pair.
+const LEFT_PAIR: u8 = 0x30;
+/// Segments B and C, the right vertical pair.
+const RIGHT_PAIR: u8 = 0x06;
+
+/// A horizontal bar graph, like a level meter, filling the display from left to right.
+///
+/// Each digit gives two steps, its left and then its right vertical segment pair.
+/// Optionally the dot after each digit and the colon between the second and third
+/// digit are steps too, giving up to 13 steps.
+///
+/// # Examples
+///
+/// ```
+/// use adafruit_7segment::{BarGraph, Frame};
+///
+/// let mut frame = Frame::new();
+///
+/// // Half full, the first two digits are lit.
+/// let bar = BarGraph::new();
+/// bar.update(&mut frame, 50, 100);
+///
+/// // Using the dots and the colon as well.
+/// let bar = BarGraph::new().dots(true).colon(true);
+/// assert_eq!(bar.steps(), 13);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BarGraph {
+    dots: bool,
+    colon: bool,
+}
+
+impl BarGraph {
+    /// Create a bar graph using the vertical segment pairs only.
+    pub fn new() -> Self {
+        BarGraph::default()
+    }
+
+    /// Use the dot after each digit as a step.
+    pub fn dots(mut self, dots: bool) -> Self {
+        self.dots = dots;
+        self
+    }
+
+    /// Use the colon as a step.
+    pub fn colon(mut self, colon: bool) -> Self {
+        self.colon = colon;
+        self
+    }
+
+    /// Number of steps in a full bar.
+    pub fn steps(&self) -> u8 {
+        8 + if self.dots { 4 } else { 0 } + self.colon as u8
+    }
+
+    /// Update the buffer with a bar showing `value` out of `max`, rounded to the nearest step.
+    /// Values above `max` show a full bar.
+    ///
+    /// # Arguments
+    ///
+    /// * `display` - The display to draw on, all digits, dots and the colon are updated.
+    /// * `value` - The level to show.
+    /// * `max` - The level of a full bar.
+    pub fn update<S, E>(&self, display: &mut S, value: u32, max: u32)
+    where
+        S: SevenSegment<E> + ?Sized,
+    {
+        let steps = u64::from(self.steps());
+        let lit = if max == 0 {
+            0
+        } else {
+            let value = u64::from(value.min(max));
+            ((value * steps * 2 + u64::from(max)) / (u64::from(max) * 2)) as u8
+        };
+
+        let mut step = 0;
+        let mut next = |on: u8| {
+            step += 1;
+            if step <= lit {
+                on
+            } else {
+                0
+            }
+        };
+        for i in 0..4 {
+            let index = Index::from(i);
+            let mut segments = next(LEFT_PAIR) | next(RIGHT_PAIR);
+            if self.dots {
+                segments |= next(1 << DOT_BIT);
+            }
+            display.update_buffer_with_segments(index, segments);
+            if index == Index::Two {
+                let colon = self.colon && next(1) != 0;
+                display.update_buffer_with_colon(colon);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DecodedBuffer, Frame};
+
+    fn shown(bar: &BarGraph, value: u32, max: u32) -> DecodedBuffer {
+        let mut frame = Frame::new();
+        bar.update(&mut frame, value, max);
+        DecodedBuffer::new(frame.display_buffer())
+    }
+
+    #[test]
+    fn half_steps() {
+        let bar = BarGraph::new();
+        assert_eq!(bar.steps(), 8);
+
+        let decoded = shown(&bar, 3, 8);
+        assert_eq!(decoded.segments(Index::One), 0b0011_0110);
+        assert_eq!(decoded.segments(Index::Two), 0b0011_0000);
+        assert_eq!(decoded.segments(Index::Three), 0b0000_0000);
+        assert!(!decoded.colon());
+
+        // 0.55 of a full bar rounds to 4 of 8 steps.
+        let decoded = shown(&bar, 55, 100);
+        assert_eq!(decoded.segments(Index::Two), 0b0011_0110);
+        assert_eq!(decoded.segments(Index::Three), 0b0000_0000);
+
+        let decoded = shown(&bar, 200, 100);
+        assert_eq!(decoded.segments(Index::Four), 0b0011_0110);
+        assert_eq!(shown(&bar, 0, 0), DecodedBuffer::default());
+    }
+
+    #[test]
+    fn dots_and_colon() {
+        let bar = BarGraph::new().dots(true).colon(true);
+
+        // Both digits with their dots, and the colon.
+        let decoded = shown(&bar, 7, 13);
+        assert_eq!(decoded.segments(Index::Two), 0b0011_0110);
+        assert!(decoded.dot(Index::Two));
+        assert!(decoded.colon());
+        assert_eq!(decoded.segments(Index::Three), 0b0000_0000);
+
+        let decoded = shown(&bar, 13, 13);
+        assert!(decoded.dot(Index::Four));
+    }
+}
@@ -0,0 +1,423 @@
+// Copyright (c) 2020 Karl Thorén <karl.h.thoren@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Command-line tool driving the 7-segment backpack through Linux i2cdev.
+//!
+//! Built with the `cli` feature. Run without arguments for the usage.
+
+use std::env;
+use std::fmt::{Debug, Write as _};
+use std::process;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use adafruit_7segment::{render, DisplayWriter, SevenSegment, Simulator, Style};
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use ht16k33::{Dimming, Display, Oscillator, HT16K33};
+use linux_embedded_hal::I2cdev;
+
+const USAGE: &str = "\
+Usage: adafruit-7segment [OPTIONS] COMMAND
+
+Commands:
+    show TEXT          Show text like \"12.34\" or \"12:05\"
+    time [OFFSET]      Show the time as HH:MM, UTC or at an offset like +2 or -5:30
+    scroll TEXT        Scroll text across the display
+    on                 Turn the display on, without blinking
+    off                Turn the display off, keeping its content
+    brightness LEVEL   Set the brightness, 0 to 15
+    blink RATE         Set the blink rate, off, 2hz, 1hz or 0.5hz
+    clear              Blank the display
+
+The display setup is kept between commands, turn the display on with `on` or `blink`.
+
+Options:
+    --bus PATH         I2C bus device, default /dev/i2c-1
+    --address ADDR     I2C address of the display, default 0x70
+    --simulate         Print the display instead of opening the I2C bus
+    --help             Print this usage
+";
+
+/// Delay between the steps of a scroll.
+const SCROLL_STEP: Duration = Duration::from_millis(300);
+
+/// A parsed command line.
+struct Options {
+    bus: String,
+    address: u8,
+    simulate: bool,
+    help: bool,
+    command: Vec<String>,
+}
+
+/// Why the tool failed, and if the usage should be printed after the message.
+#[derive(Debug, PartialEq)]
+struct Failure {
+    message: String,
+    usage: bool,
+}
+
+impl Failure {
+    /// A failure from a missing argument, printed with the usage.
+    fn missing(message: impl Into<String>) -> Self {
+        Failure {
+            message: message.into(),
+            usage: true,
+        }
+    }
+}
+
+impl From<String> for Failure {
+    fn from(message: String) -> Self {
+        Failure {
+            message,
+            usage: false,
+        }
+    }
+}
+
+impl From<&str> for Failure {
+    fn from(message: &str) -> Self {
+        Failure::from(String::from(message))
+    }
+}
+
+fn parse_address(text: &str) -> Result<u8, String> {
+    let parsed = match text.strip_prefix("0x") {
+        Some(hex) => u8::from_str_radix(hex, 16),
+        None => text.parse(),
+    };
+    parsed.map_err(|_| format!("Invalid address: {}", text))
+}
+
+fn parse_options(args: impl Iterator<Item = String>) -> Result<Options, Failure> {
+    let mut options = Options {
+        bus: String::from("/dev/i2c-1"),
+        address: 0x70,
+        simulate: false,
+        help: false,
+        command: Vec::new(),
+    };
+    let mut args = args;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bus" => {
+                options.bus = args
+                    .next()
+                    .ok_or_else(|| Failure::missing("Missing bus path"))?
+            }
+            "--address" => {
+                let address = args
+                    .next()
+                    .ok_or_else(|| Failure::missing("Missing address"))?;
+                options.address = parse_address(&address)?;
+            }
+            "--simulate" => options.simulate = true,
+            "--help" => options.help = true,
+            _ if arg.starts_with("--") => return Err(format!("Unknown option: {}", arg).into()),
+            _ => options.command.push(arg),
+        }
+    }
+    Ok(options)
+}
+
+/// Show text from the start of a new frame, like `DisplayWriter` does.
+fn show<S, E>(display: &mut S, text: &str) -> Result<(), String>
+where
+    S: SevenSegment<E>,
+{
+    let mut writer = DisplayWriter::new(display);
+    writer
+        .write_str(text)
+        .map_err(|_| format!("Text does not fit on the display: {}", text))
+}
+
+/// Parse an offset from UTC like `+2`, `-5:30` or `0`, in minutes.
+fn parse_utc_offset(text: &str) -> Result<i64, String> {
+    let invalid = || format!("Invalid UTC offset: {}", text);
+    let (sign, offset) = match text.strip_prefix('-') {
+        Some(offset) => (-1, offset),
+        None => (1, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let mut parts = offset.splitn(2, ':');
+    let hours: i64 = parts
+        .next()
+        .and_then(|hours| hours.parse().ok())
+        .ok_or_else(invalid)?;
+    let minutes: i64 = match parts.next() {
+        Some(minutes) => minutes.parse().map_err(|_| invalid())?,
+        None => 0,
+    };
+    if hours > 14 || minutes > 59 {
+        return Err(invalid());
+    }
+    Ok(sign * (hours * 60 + minutes))
+}
+
+/// Split text into the characters shown on each digit while scrolling.
+/// Dots and the colon take no digit of their own, they go with the character before them.
+fn scroll_cells(text: &str) -> Vec<String> {
+    let mut cells: Vec<String> = Vec::new();
+    for c in text.chars() {
+        match cells.last_mut() {
+            Some(cell) if (c == '.' || c == ':') && !cell.contains(c) => cell.push(c),
+            _ => cells.push(c.to_string()),
+        }
+    }
+    cells
+}
+
+fn blink_rate(rate: &str) -> Result<Display, String> {
+    match rate {
+        "off" => Ok(Display::ON),
+        "2hz" => Ok(Display::TWO_HZ),
+        "1hz" => Ok(Display::ONE_HZ),
+        "0.5hz" => Ok(Display::HALF_HZ),
+        _ => Err(format!("Invalid blink rate: {}", rate)),
+    }
+}
+
+/// Run a command, calling `shown` after each change of the display and `delay` between
+/// the steps of a scroll.
+fn run<I2C, E>(
+    ht16k33: &mut HT16K33<I2C>,
+    command: &[String],
+    mut shown: impl FnMut(&HT16K33<I2C>),
+    mut delay: impl FnMut(Duration),
+) -> Result<(), Failure>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+    E: Debug,
+{
+    let bus = |error: E| format!("I2C error: {:?}", error);
+    let argument = |i: usize| {
+        command
+            .get(i)
+            .map(String::as_str)
+            .ok_or_else(|| Failure::missing(format!("Missing argument for {}", command[0])))
+    };
+
+    ht16k33.set_oscillator(Oscillator::ON).map_err(bus)?;
+    match command.first().map(String::as_str) {
+        Some("show") => show(ht16k33, argument(1)?)?,
+        Some("time") => {
+            let offset = match command.get(1) {
+                Some(offset) => parse_utc_offset(offset)?,
+                None => 0,
+            };
+            let seconds = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_err(|_| "The clock is before 1970")?
+                .as_secs();
+            let minutes = (seconds as i64 / 60 + offset).rem_euclid(24 * 60);
+            show(
+                ht16k33,
+                &format!("{:02}:{:02}", minutes / 60 % 24, minutes % 60),
+            )?;
+        }
+        Some("scroll") => {
+            // Enter from the right and leave to the left, one digit per step.
+            let cells = scroll_cells(&format!("    {}    ", argument(1)?));
+            for window in cells.windows(4) {
+                show(ht16k33, &window.concat())?;
+                ht16k33.write_display_buffer().map_err(bus)?;
+                shown(ht16k33);
+                delay(SCROLL_STEP);
+            }
+            return Ok(());
+        }
+        Some("brightness") => {
+            let level = argument(1)?
+                .parse()
+                .ok()
+                .and_then(|level| Dimming::from_u8(level).ok())
+                .ok_or("The brightness must be 0 to 15")?;
+            ht16k33.set_dimming(level).map_err(bus)?;
+            shown(ht16k33);
+            return Ok(());
+        }
+        Some(setup @ "on") | Some(setup @ "off") => {
+            let display = if setup == "on" {
+                Display::ON
+            } else {
+                Display::OFF
+            };
+            ht16k33.set_display(display).map_err(bus)?;
+            shown(ht16k33);
+            return Ok(());
+        }
+        Some("blink") => {
+            ht16k33
+                .set_display(blink_rate(argument(1)?)?)
+                .map_err(bus)?;
+            shown(ht16k33);
+            return Ok(());
+        }
+        Some("clear") => {
+            ht16k33.clear_display_buffer();
+            ht16k33.write_display_buffer().map_err(bus)?;
+            shown(ht16k33);
+            return Ok(());
+        }
+        Some(other) => return Err(format!("Unknown command: {}", other).into()),
+        None => return Err(Failure::missing("Missing command")),
+    }
+    ht16k33.write_display_buffer().map_err(bus)?;
+    shown(ht16k33);
+    Ok(())
+}
+
+/// Print the simulated display and its settings.
+fn print_simulated(ht16k33: &HT16K33<Simulator>) {
+    let blink = match *ht16k33.display() {
+        Display::OFF => "display off",
+        Display::TWO_HZ => "blink 2hz",
+        Display::ONE_HZ => "blink 1hz",
+        Display::HALF_HZ => "blink 0.5hz",
+        _ => "display on",
+    };
+    print!("{}", render(ht16k33.display_buffer(), Style::Unicode));
+    println!("{}, brightness {}\n", blink, ht16k33.dimming().bits());
+}
+
+fn main() {
+    let result = parse_options(env::args().skip(1)).and_then(|options| {
+        if options.help {
+            print!("{}", USAGE);
+            Ok(())
+        } else if options.simulate {
+            let mut ht16k33 = HT16K33::new(Simulator::new(options.address), options.address);
+            run(
+                &mut ht16k33,
+                &options.command,
+                print_simulated,
+                thread::sleep,
+            )
+        } else {
+            let i2c = I2cdev::new(&options.bus)
+                .map_err(|error| format!("Could not open {}: {}", options.bus, error))?;
+            let mut ht16k33 = HT16K33::new(i2c, options.address);
+            run(&mut ht16k33, &options.command, |_| {}, thread::sleep)
+        }
+    });
+    if let Err(failure) = result {
+        if failure.usage {
+            eprintln!("{}\n\n{}", failure.message, USAGE);
+        } else {
+            eprintln!("{}", failure.message);
+        }
+        process::exit(2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adafruit_7segment::DecodedBuffer;
+
+    fn args(line: &str) -> impl Iterator<Item = String> + '_ {
+        line.split(' ').map(String::from)
+    }
+
+    #[test]
+    fn options() {
+        let options = parse_options(args("--address 0x71 --simulate show 12.34")).unwrap();
+        assert_eq!(options.address, 0x71);
+        assert!(options.simulate);
+        assert_eq!(options.command, ["show", "12.34"]);
+        assert!(parse_options(args("--address 300 clear")).is_err());
+        assert_eq!(
+            parse_options(args("--adress 0x71 clear")).err().unwrap(),
+            Failure::from("Unknown option: --adress")
+        );
+        assert!(parse_options(args("--help")).unwrap().help);
+        assert_eq!(
+            parse_options(args("--simulate --address")).err().unwrap(),
+            Failure::missing("Missing address")
+        );
+        assert_eq!(
+            parse_options(args("show -1.5")).unwrap().command,
+            ["show", "-1.5"]
+        );
+    }
+
+    #[test]
+    fn utc_offset() {
+        assert_eq!(parse_utc_offset("+2"), Ok(120));
+        assert_eq!(parse_utc_offset("2"), Ok(120));
+        assert_eq!(parse_utc_offset("-5:30"), Ok(-330));
+        assert_eq!(parse_utc_offset("0"), Ok(0));
+        assert!(parse_utc_offset("+15").is_err());
+        assert!(parse_utc_offset("1:60").is_err());
+        assert!(parse_utc_offset("local").is_err());
+    }
+
+    #[test]
+    fn scroll_by_digit() {
+        assert_eq!(scroll_cells("1.5.:2"), ["1.", "5.:", "2"]);
+        assert_eq!(scroll_cells("..a"), [".", ".", "a"]);
+
+        let mut ht16k33 = HT16K33::new(Simulator::new(0x70), 0x70);
+        let mut shown = Vec::new();
+        let mut steps = 0;
+        let command: Vec<String> = args("scroll 1.2").collect();
+        run(
+            &mut ht16k33,
+            &command,
+            |ht16k33| shown.push(DecodedBuffer::new(ht16k33.display_buffer()).to_string()),
+            |step| {
+                assert_eq!(step, SCROLL_STEP);
+                steps += 1;
+            },
+        )
+        .unwrap();
+        assert_eq!(steps, 7);
+        assert_eq!(
+            shown,
+            ["    ", "   1.", "  1.2", " 1.2 ", "1.2  ", "2   ", "    "]
+        );
+    }
+
+    #[test]
+    fn commands() {
+        let mut ht16k33 = HT16K33::new(Simulator::new(0x70), 0x70);
+        let mut shown = Vec::new();
+
+        let command: Vec<String> = args("show 12:05").collect();
+        run(
+            &mut ht16k33,
+            &command,
+            |ht16k33| shown.push(DecodedBuffer::new(ht16k33.display_buffer()).to_string()),
+            |_| {},
+        )
+        .unwrap();
+        let command: Vec<String> = args("blink 2hz").collect();
+        run(&mut ht16k33, &command, |_| {}, |_| {}).unwrap();
+        // Showing text keeps the blinking.
+        let command: Vec<String> = args("show 12:05").collect();
+        run(&mut ht16k33, &command, |_| {}, |_| {}).unwrap();
+        let command: Vec<String> = args("brightness 16").collect();
+        assert_eq!(
+            run(&mut ht16k33, &command, |_| {}, |_| {}),
+            Err(Failure::from("The brightness must be 0 to 15"))
+        );
+        let command: Vec<String> = args("blink").collect();
+        assert_eq!(
+            run(&mut ht16k33, &command, |_| {}, |_| {}),
+            Err(Failure::missing("Missing argument for blink"))
+        );
+
+        assert_eq!(shown, ["12:05"]);
+        let simulator = ht16k33.destroy();
+        assert_eq!(simulator.display(), Display::TWO_HZ);
+        assert_eq!(
+            DecodedBuffer::new(simulator.display_buffer()).to_string(),
+            "12:05"
+        );
+    }
+}
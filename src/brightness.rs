@@ -0,0 +1,260 @@
+// Copyright (c) 2020 Karl Thorén <karl.h.thoren@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Perceptual brightness on top of the 16 dimming levels of the HT16K33, and fading.
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use ht16k33::{Dimming, HT16K33};
+
+use crate::Error;
+
+/// Perceived brightness of each dimming level, `255 * ((level + 1) / 16)^(1 / 2.2)`.
+///
+/// The dimming levels set the duty cycle, which the eye sees roughly with a gamma of 2.2.
+const DIMMING_CURVE: [u8; 16] = [
+    72, 99, 119, 136, 150, 163, 175, 186, 196, 206, 215, 224, 232, 240, 248, 255,
+];
+
+/// Brightness as perceived by the eye, from 0 to 255.
+///
+/// The HT16K33 can not dim the display to dark, brightness below the lowest dimming
+/// level gives that level. Turn the display off to make it dark.
+///
+/// # Examples
+///
+/// ```
+/// use ht16k33::Dimming;
+/// use adafruit_7segment::Brightness;
+///
+/// // Half as bright as full brightness looks like a quarter of the duty cycle.
+/// assert_eq!(Brightness::from_percent(50).dimming(), Dimming::BRIGHTNESS_4_16);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Brightness(u8);
+
+impl Brightness {
+    /// Create a brightness from 0 to 255.
+    pub const fn new(level: u8) -> Self {
+        Brightness(level)
+    }
+
+    /// Create a brightness from 0 to 100 percent, higher values are full brightness.
+    pub fn from_percent(percent: u8) -> Self {
+        let percent = u16::from(percent.min(100));
+        Brightness(((percent * 255 + 50) / 100) as u8)
+    }
+
+    /// The brightness that a dimming level looks like.
+    pub fn from_dimming(dimming: Dimming) -> Self {
+        Brightness(DIMMING_CURVE[usize::from(dimming.bits() & 0x0F)])
+    }
+
+    /// The brightness from 0 to 255.
+    pub fn level(self) -> u8 {
+        self.0
+    }
+
+    /// The dimming level that looks closest to this brightness.
+    pub fn dimming(self) -> Dimming {
+        let level = DIMMING_CURVE
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, &perceived)| (i16::from(perceived) - i16::from(self.0)).abs())
+            .map_or(0, |(level, _)| level as u8);
+        Dimming::from_bits_truncate(level)
+    }
+}
+
+/// Trait for setting the brightness of the display as perceived by the eye.
+pub trait BrightnessControl<E> {
+    /// Set the dimming level closest to the brightness.
+    fn set_brightness(&mut self, brightness: Brightness) -> Result<(), Error>;
+}
+
+impl<I2C, E> BrightnessControl<E> for HT16K33<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Set the dimming level closest to the brightness.
+    /// Unlike the update methods this sends the dimming to the device right away.
+    ///
+    /// # Arguments
+    ///
+    /// * `brightness` - The brightness as perceived by the eye.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ht16k33::i2c_mock::I2cMock;
+    /// use ht16k33::HT16K33;
+    /// use adafruit_7segment::{Brightness, BrightnessControl};
+    ///
+    /// // Create an I2C device.
+    /// let mut i2c = I2cMock::new();
+    ///
+    /// // The I2C device address.
+    /// const DISP_I2C_ADDR: u8 = 112;
+    ///
+    /// let mut ht16k33 = HT16K33::new(i2c, DISP_I2C_ADDR);
+    ///
+    /// ht16k33.set_brightness(Brightness::from_percent(30)).expect("Could not set dimming!");
+    /// ```
+    fn set_brightness(&mut self, brightness: Brightness) -> Result<(), Error> {
+        self.set_dimming(brightness.dimming())
+            .map_err(|_| Error::Bus)
+    }
+}
+
+/// A fade between two brightnesses, advanced by ticks without blocking.
+///
+/// The brightness changes linearly as perceived by the eye. Each tick returns the
+/// dimming level to set, only when it differs from the last one returned.
+///
+/// # Examples
+///
+/// ```
+/// use ht16k33::i2c_mock::I2cMock;
+/// use ht16k33::HT16K33;
+/// use adafruit_7segment::{Brightness, Fade};
+///
+/// // Create an I2C device.
+/// let mut i2c = I2cMock::new();
+///
+/// // The I2C device address.
+/// const DISP_I2C_ADDR: u8 = 112;
+///
+/// let mut ht16k33 = HT16K33::new(i2c, DISP_I2C_ADDR);
+///
+/// // Fade in over 50 ticks, for example 1 second with a tick every 20 ms.
+/// let mut fade = Fade::new(Brightness::new(0), Brightness::new(255), 50);
+/// while !fade.is_done() {
+///     if let Some(dimming) = fade.tick() {
+///         ht16k33.set_dimming(dimming).expect("Could not set dimming!");
+///     }
+///     // Do other work until the next tick.
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fade {
+    from: Brightness,
+    to: Brightness,
+    duration_ticks: u32,
+    elapsed_ticks: u32,
+    // The dimming level last returned.
+    dimming: Option<Dimming>,
+}
+
+impl Fade {
+    /// Create a fade from one brightness to another over a number of ticks.
+    pub fn new(from: Brightness, to: Brightness, duration_ticks: u32) -> Self {
+        Fade {
+            from,
+            to,
+            duration_ticks,
+            elapsed_ticks: 0,
+            dimming: None,
+        }
+    }
+
+    /// The brightness reached so far.
+    pub fn brightness(&self) -> Brightness {
+        if self.elapsed_ticks >= self.duration_ticks {
+            return self.to;
+        }
+        let from = i64::from(self.from.level());
+        let to = i64::from(self.to.level());
+        let step = (to - from) * i64::from(self.elapsed_ticks) / i64::from(self.duration_ticks);
+        Brightness::new((from + step) as u8)
+    }
+
+    /// Whether the fade has reached its end.
+    pub fn is_done(&self) -> bool {
+        self.elapsed_ticks >= self.duration_ticks
+    }
+
+    /// Advance the fade by one tick.
+    /// Returns the dimming level to set, `None` if it is the same as last time.
+    pub fn tick(&mut self) -> Option<Dimming> {
+        if self.elapsed_ticks < self.duration_ticks {
+            self.elapsed_ticks += 1;
+        }
+        let dimming = self.brightness().dimming();
+        if self.dimming == Some(dimming) {
+            None
+        } else {
+            self.dimming = Some(dimming);
+            Some(dimming)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use self::std::vec;
+    use self::std::vec::Vec;
+    use super::*;
+    use crate::Simulator;
+
+    #[test]
+    fn gamma_curve() {
+        assert_eq!(
+            Brightness::from_percent(0).dimming(),
+            Dimming::BRIGHTNESS_MIN
+        );
+        assert_eq!(
+            Brightness::from_percent(100).dimming(),
+            Dimming::BRIGHTNESS_MAX
+        );
+        assert_eq!(Brightness::from_percent(200), Brightness::new(255));
+        assert_eq!(Brightness::from_percent(50).level(), 128);
+        assert_eq!(Brightness::new(99).dimming(), Dimming::BRIGHTNESS_2_16);
+        assert_eq!(Brightness::new(200).dimming(), Dimming::BRIGHTNESS_9_16);
+        assert_eq!(
+            Brightness::from_dimming(Dimming::BRIGHTNESS_MIN),
+            Brightness::new(72)
+        );
+
+        // Monotonic over the whole range.
+        let levels: Vec<u8> = (0..=255)
+            .map(|level| Brightness::new(level).dimming().bits())
+            .collect();
+        assert!(levels.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn set_brightness() {
+        let mut ht16k33 = HT16K33::new(Simulator::new(0x70), 0x70);
+        ht16k33
+            .set_brightness(Brightness::from_percent(75))
+            .unwrap();
+        assert_eq!(ht16k33.destroy().dimming(), Dimming::BRIGHTNESS_8_16);
+    }
+
+    #[test]
+    fn fade() {
+        let mut fade = Fade::new(Brightness::new(255), Brightness::new(72), 4);
+        let mut dimmings = Vec::new();
+        while !fade.is_done() {
+            dimmings.push(fade.tick().map(|dimming| dimming.bits()));
+        }
+        // 210, 164, 118 and 72 perceived.
+        assert_eq!(dimmings, vec![Some(9), Some(5), Some(2), Some(0)]);
+        assert_eq!(fade.tick(), None);
+        assert_eq!(fade.brightness(), Brightness::new(72));
+
+        let mut fade = Fade::new(Brightness::new(0), Brightness::new(10), 2);
+        assert_eq!(fade.tick(), Some(Dimming::BRIGHTNESS_MIN));
+        assert_eq!(fade.tick(), None);
+        assert!(fade.is_done());
+
+        let mut fade = Fade::new(Brightness::new(0), Brightness::new(255), 0);
+        assert!(fade.is_done());
+        assert_eq!(fade.tick(), Some(Dimming::BRIGHTNESS_MAX));
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright (c) 2020 Karl Thorén <karl.h.thoren@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Decoding of the display RAM back into digits, dots and the colon.
+
+use core::fmt::{self, Write};
+
+use ht16k33::{DisplayData, ROWS_SIZE};
+
+use crate::number::DEGREE_SIGN;
+use crate::{
+    digit_position, Index, COLON_BIT, DOT_BIT, HEX_NUMBER_FONT_TABLE, MEGA_SIGN, MINUS_SIGN,
+};
+
+/// The characters of `HEX_NUMBER_FONT_TABLE`, in the case their glyphs look like.
+const FONT_CHARS: &[u8; 36] = b"0123456789AbCdEFGhiJkLmnoPqrStUvwXyz";
+
+/// The character a digit shows, `?` if the segments do not form a known glyph.
+/// Glyphs shared by a digit and a letter, like 5 and S, decode as the digit.
+fn glyph_char(segments: u8) -> char {
+    match segments {
+        0 => ' ',
+        MINUS_SIGN => '-',
+        DEGREE_SIGN => '°',
+        MEGA_SIGN => 'M',
+        _ => HEX_NUMBER_FONT_TABLE
+            .iter()
+            .position(|&glyph| glyph == segments)
+            .map_or('?', |i| char::from(FONT_CHARS[i])),
+    }
+}
+
+/// Digits, dots and colon decoded from a display buffer.
+///
+/// Works on the buffer of both the `HT16K33` and a [`Frame`](crate::Frame).
+/// Formatting it with `{}` gives the text on the display, a digit is shown as its
+/// character, followed by `.` if its dot is on, with `:` between the second and third
+/// digit if the colon is on. Unknown segment patterns are shown as `?`.
+///
+/// # Examples
+///
+/// ```
+/// use adafruit_7segment::{DecodedBuffer, Frame, Index, SevenSegment};
+///
+/// let mut frame = Frame::new();
+/// frame.update_buffer_with_float(Index::One, -1.5, 1, 10).unwrap();
+/// frame.update_buffer_with_colon(true);
+///
+/// let decoded = DecodedBuffer::new(frame.display_buffer());
+/// assert!(decoded.colon());
+/// assert!(!decoded.dot(Index::One));
+/// assert_eq!(format!("{}", decoded), " -:1.5");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DecodedBuffer {
+    segments: [u8; 4],
+    dots: [bool; 4],
+    colon: bool,
+    colon_row: u8,
+}
+
+impl DecodedBuffer {
+    /// Decode a display buffer.
+    pub fn new(buffer: &[DisplayData; ROWS_SIZE]) -> Self {
+        let mut decoded = DecodedBuffer::default();
+        for i in 0..4 {
+            let bits = buffer[usize::from(digit_position(Index::from(i)) * 2)].bits();
+            decoded.segments[usize::from(i)] = bits & !(1 << DOT_BIT);
+            decoded.dots[usize::from(i)] = bits & (1 << DOT_BIT) != 0;
+        }
+        // The colon is at address 2.
+        decoded.colon_row = buffer[4].bits();
+        decoded.colon = decoded.colon_row & (1 << COLON_BIT) != 0;
+        decoded
+    }
+
+    /// Segments lit on the digit at the specified index, bit 0 is segment A and bit 6 segment G.
+    pub fn segments(&self, index: Index) -> u8 {
+        self.segments[usize::from(u8::from(index))]
+    }
+
+    /// Whether the dot at the specified index is on.
+    pub fn dot(&self, index: Index) -> bool {
+        self.dots[usize::from(u8::from(index))]
+    }
+
+    /// Whether the colon is on.
+    pub fn colon(&self) -> bool {
+        self.colon
+    }
+
+    /// All bits of the colon address. Bit 1 is the colon, on the 1.2" backpack
+    /// bit 2 and 3 are the upper and lower dot of the left colon and bit 4 is the upper dot.
+    pub fn colon_row(&self) -> u8 {
+        self.colon_row
+    }
+}
+
+impl fmt::Display for DecodedBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for i in 0..4 {
+            let index = Index::from(i);
+            if index == Index::Three && self.colon {
+                f.write_char(':')?;
+            }
+            f.write_char(glyph_char(self.segments(index)))?;
+            if self.dot(index) {
+                f.write_char('.')?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use self::std::string::ToString;
+    use super::*;
+    use crate::{Frame, SevenSegment};
+    use embedded_hal_mock::i2c::Mock as I2cMock;
+    use ht16k33::HT16K33;
+
+    #[test]
+    fn decode_text() {
+        let mut frame = Frame::new();
+        frame.update_buffer_with_hex(0x5EAF);
+        frame.update_buffer_with_dot(Index::Four, true);
+        assert_eq!(
+            DecodedBuffer::new(frame.display_buffer()).to_string(),
+            "5EAF."
+        );
+
+        frame.update_buffer_with_segments(Index::One, 0b0100_1001);
+        frame.update_buffer_with_colon(true);
+        assert_eq!(
+            DecodedBuffer::new(frame.display_buffer()).to_string(),
+            "?E:AF."
+        );
+
+        let mut ht16k33 = HT16K33::new(I2cMock::new(&[]), 0);
+        ht16k33
+            .update_buffer_with_measurement(Index::One, -4., crate::Unit::Celsius)
+            .unwrap();
+        assert_eq!(
+            DecodedBuffer::new(ht16k33.display_buffer()).to_string(),
+            "-4°C"
+        );
+        ht16k33.destroy().done();
+    }
+}
@@ -0,0 +1,295 @@
+// Copyright (c) 2020 Karl Thorén <karl.h.thoren@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Front and back buffers, so partially drawn frames are never sent to the display.
+
+#[cfg(feature = "critical-section")]
+use core::cell::RefCell;
+
+#[cfg(feature = "critical-section")]
+use critical_section::Mutex;
+use embedded_hal::blocking::i2c::Write;
+
+use crate::Frame;
+
+/// A pair of frames: drawing goes to the back buffer and only the front buffer is sent.
+///
+/// [`DoubleBuffer::commit`] copies the back buffer to the front buffer in one step,
+/// the back buffer keeps its content so the next frame can be drawn on top of it.
+/// A flush therefore always sends a completely drawn frame, even when it comes between
+/// drawing steps of the next one. Both buffers are owned by the `DoubleBuffer`, to flush
+/// from an interrupt handler while drawing elsewhere draw on a `Frame` and commit it to a
+/// `FrontBuffer` instead, with the `critical-section` feature.
+///
+/// # Examples
+///
+/// ```
+/// use ht16k33::i2c_mock::I2cMock;
+/// use adafruit_7segment::{DoubleBuffer, Index, SevenSegment};
+///
+/// // Create an I2C device.
+/// let mut i2c = I2cMock::new();
+///
+/// // The I2C device address.
+/// const DISP_I2C_ADDR: u8 = 112;
+///
+/// let mut display = DoubleBuffer::new();
+///
+/// // Draw on the back buffer, nothing is shown yet.
+/// display.back_mut().update_buffer_with_float(Index::One, 23.5, 1, 10).expect("Failed to encode float to buffer!");
+///
+/// // Show the new frame.
+/// display.present(&mut i2c, DISP_I2C_ADDR).expect("Could not write the display RAM!");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DoubleBuffer {
+    front: Frame,
+    back: Frame,
+}
+
+impl DoubleBuffer {
+    /// Create a double buffer with both frames empty.
+    pub fn new() -> Self {
+        DoubleBuffer::default()
+    }
+
+    /// The frame last committed, this is what a flush sends.
+    pub fn front(&self) -> &Frame {
+        &self.front
+    }
+
+    /// The frame being drawn.
+    pub fn back(&self) -> &Frame {
+        &self.back
+    }
+
+    /// The frame being drawn, to draw on with the [`SevenSegment`](crate::SevenSegment) methods.
+    pub fn back_mut(&mut self) -> &mut Frame {
+        &mut self.back
+    }
+
+    /// Copy the back buffer to the front buffer, without sending anything.
+    pub fn commit(&mut self) {
+        self.front.copy_from(&self.back);
+    }
+
+    /// Send the changes of the front buffer to the display, see [`Frame::flush`].
+    pub fn flush<I2C, E>(&mut self, i2c: &mut I2C, address: u8) -> Result<(), E>
+    where
+        I2C: Write<Error = E>,
+    {
+        self.front.flush(i2c, address)
+    }
+
+    /// Commit the back buffer and send the changes to the display.
+    ///
+    /// # Arguments
+    ///
+    /// * `i2c` - The I2C bus the display is on, only borrowed during the flush.
+    /// * `address` - The I2C address of the display.
+    pub fn present<I2C, E>(&mut self, i2c: &mut I2C, address: u8) -> Result<(), E>
+    where
+        I2C: Write<Error = E>,
+    {
+        self.commit();
+        self.flush(i2c, address)
+    }
+}
+
+/// A front buffer in a `critical_section::Mutex`, flushed by an interrupt handler while the
+/// main loop draws the next frame on a [`Frame`] of its own.
+///
+/// Committing copies a completely drawn frame in one critical section, so a flush never
+/// sends a half drawn frame. Needs the `critical-section` feature, and a critical section
+/// implementation for the target.
+///
+/// # Examples
+///
+/// ```
+/// use ht16k33::i2c_mock::I2cMock;
+/// use adafruit_7segment::{Frame, FrontBuffer, Index, SevenSegment};
+///
+/// static FRONT: FrontBuffer = FrontBuffer::new();
+///
+/// // Create an I2C device.
+/// let mut i2c = I2cMock::new();
+///
+/// // The I2C device address.
+/// const DISP_I2C_ADDR: u8 = 112;
+///
+/// // The back buffer, owned by the main loop.
+/// let mut back = Frame::new();
+/// back.update_buffer_with_float(Index::One, 23.5, 1, 10).expect("Failed to encode float to buffer!");
+/// FRONT.commit(&back);
+///
+/// // For example from a timer interrupt.
+/// FRONT.flush(&mut i2c, DISP_I2C_ADDR).expect("Could not write the display RAM!");
+/// ```
+#[cfg(feature = "critical-section")]
+pub struct FrontBuffer {
+    frame: Mutex<RefCell<Frame>>,
+}
+
+#[cfg(feature = "critical-section")]
+impl Default for FrontBuffer {
+    fn default() -> Self {
+        FrontBuffer::new()
+    }
+}
+
+#[cfg(feature = "critical-section")]
+impl FrontBuffer {
+    /// Create an empty front buffer.
+    pub const fn new() -> Self {
+        FrontBuffer {
+            frame: Mutex::new(RefCell::new(Frame::new())),
+        }
+    }
+
+    /// A copy of the frame last committed.
+    pub fn frame(&self) -> Frame {
+        critical_section::with(|cs| *self.frame.borrow_ref(cs))
+    }
+
+    /// Copy a completely drawn back buffer to the front buffer, without sending anything.
+    pub fn commit(&self, back: &Frame) {
+        critical_section::with(|cs| self.frame.borrow_ref_mut(cs).copy_from(back));
+    }
+
+    /// Send the changes of the front buffer to the display, see [`Frame::flush`].
+    ///
+    /// The frame is copied in a critical section and sent outside of it, a commit during
+    /// the transfer is sent by the next flush.
+    ///
+    /// # Arguments
+    ///
+    /// * `i2c` - The I2C bus the display is on, only borrowed during the flush.
+    /// * `address` - The I2C address of the display.
+    pub fn flush<I2C, E>(&self, i2c: &mut I2C, address: u8) -> Result<(), E>
+    where
+        I2C: Write<Error = E>,
+    {
+        let mut snapshot = critical_section::with(|cs| {
+            let mut front = self.frame.borrow_ref_mut(cs);
+            let snapshot = *front;
+            front.take_dirty();
+            snapshot
+        });
+        let result = snapshot.flush(i2c, address);
+        if result.is_err() {
+            let (rows, blink) = snapshot.take_dirty();
+            critical_section::with(|cs| self.frame.borrow_ref_mut(cs).mark_dirty(rows, blink));
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use self::std::vec;
+    use super::*;
+    use crate::{Index, SevenSegment};
+    use embedded_hal_mock::i2c::{Mock as I2cMock, Transaction};
+    use ht16k33::ROWS_SIZE;
+
+    const ADDRESS: u8 = 0;
+
+    #[test]
+    fn flush_sends_front_only() {
+        let mut all_rows = vec![0x00, 0b0000_0110];
+        all_rows.extend([0; ROWS_SIZE - 1].iter().cloned());
+        let expectations = [
+            Transaction::write(ADDRESS, all_rows),
+            Transaction::write(ADDRESS, vec![0x08, 0b0110_0110]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut display = DoubleBuffer::new();
+        display.back_mut().update_buffer_with_digit(Index::One, 1);
+        assert!(display.present(&mut i2c, ADDRESS).is_ok());
+        assert_eq!(display.front().display_buffer()[0].bits(), 0b0000_0110);
+
+        // A half drawn frame is not sent.
+        display.back_mut().update_buffer_with_digit(Index::Four, 4);
+        assert!(display.flush(&mut i2c, ADDRESS).is_ok());
+        assert_eq!(display.front().display_buffer()[8].bits(), 0b0000_0000);
+
+        // The back buffer keeps its content after the commit.
+        assert!(display.present(&mut i2c, ADDRESS).is_ok());
+        assert_eq!(
+            display.back().display_buffer(),
+            display.front().display_buffer()
+        );
+        assert!(display.present(&mut i2c, ADDRESS).is_ok());
+
+        i2c.done();
+    }
+
+    #[test]
+    #[cfg(feature = "critical-section")]
+    fn front_buffer_between_draws() {
+        let mut all_rows = vec![0x00];
+        all_rows.extend([0; ROWS_SIZE].iter().cloned());
+        let expectations = [
+            Transaction::write(ADDRESS, all_rows),
+            Transaction::write(ADDRESS, vec![0x00, 0b0000_0110, 0, 0b0101_1011]),
+            Transaction::write(ADDRESS, vec![0x06, 0b0100_1111, 0, 0b0110_0110]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let front = FrontBuffer::new();
+        let mut back = Frame::new();
+
+        // Flushes between the draws of a frame send the frame committed before.
+        back.update_buffer_with_digit(Index::One, 1);
+        back.update_buffer_with_digit(Index::Two, 2);
+        assert!(front.flush(&mut i2c, ADDRESS).is_ok());
+        back.update_buffer_with_digit(Index::Three, 3);
+        assert!(front.flush(&mut i2c, ADDRESS).is_ok());
+        back.update_buffer_with_digit(Index::Four, 4);
+
+        // Only the completely drawn frame is sent.
+        front.commit(&back);
+        assert!(front.flush(&mut i2c, ADDRESS).is_ok());
+        assert_eq!(front.frame().display_buffer(), back.display_buffer());
+
+        i2c.done();
+    }
+
+    #[test]
+    #[cfg(feature = "critical-section")]
+    fn front_buffer_flushed_from_another_thread() {
+        use crate::{DecodedBuffer, Simulator};
+        use std::string::ToString;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::thread;
+
+        let front = FrontBuffer::new();
+        let done = AtomicBool::new(false);
+        thread::scope(|scope| {
+            // The interrupt handler, every state of the display is a whole frame.
+            scope.spawn(|| {
+                let mut simulator = Simulator::new(ADDRESS);
+                while !done.load(Ordering::Relaxed) {
+                    front.flush(&mut simulator, ADDRESS).unwrap();
+                    let text = DecodedBuffer::new(simulator.display_buffer()).to_string();
+                    let first = text.chars().next().unwrap();
+                    assert!(text.chars().all(|c| c == first), "half drawn: {}", text);
+                }
+            });
+            let mut back = Frame::new();
+            for value in 0..200 {
+                for index in 0..4 {
+                    back.update_buffer_with_digit(Index::from(index), value % 10);
+                }
+                front.commit(&back);
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+    }
+}
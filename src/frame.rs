@@ -0,0 +1,543 @@
+// Copyright (c) 2020 Karl Thorén <karl.h.thoren@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A display buffer that is not tied to an I2C bus.
+
+use core::convert::Infallible;
+
+use embedded_hal::blocking::i2c::Write;
+use ht16k33::{Dimming, Display, DisplayData, DisplayDataAddress, Oscillator, ROWS_SIZE};
+
+use crate::{
+    char_bits, digit_position, AsciiChar, Error, Index, Overflow, SevenSegment, COLON_BIT, DOT_BIT,
+    HEX_NUMBER_FONT_TABLE,
+};
+
+/// The display RAM image of the 7-segment backpack, together with display-wide settings.
+///
+/// A frame is drawn on with the same [`SevenSegment`] methods as the `HT16K33`,
+/// but it does not own the I2C bus. The rows changed since the last transfer are tracked,
+/// so [`Frame::flush`] only sends what changed.
+///
+/// The frame also holds the display-wide settings that the `HT16K33` of the ht16k33 crate
+/// has no room for: the overflow policy of the numeric methods, and the blinking requested
+/// by `Overflow::ClampBlink`, which the next flush sends.
+///
+/// # Examples
+///
+/// ```
+/// use adafruit_7segment::{Frame, Index, Overflow, SevenSegment};
+///
+/// let mut frame = Frame::new();
+/// // Show dashes instead of returning an error for numbers that do not fit.
+/// frame.set_overflow(Overflow::Dashes);
+/// frame.update_buffer_with_float(Index::One, 12345., 0, 10).unwrap();
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frame {
+    buffer: [DisplayData; ROWS_SIZE],
+    overflow: Overflow,
+    blink: bool,
+    // The blinking changed since the last flush or setup.
+    blink_changed: bool,
+    // One bit per row changed since the last flush.
+    dirty: u16,
+}
+
+impl Default for Frame {
+    fn default() -> Frame {
+        Frame::new()
+    }
+}
+
+impl Frame {
+    /// Create an empty frame.
+    pub const fn new() -> Self {
+        Frame {
+            buffer: [DisplayData::empty(); ROWS_SIZE],
+            overflow: Overflow::Error,
+            blink: false,
+            blink_changed: false,
+            // The content of the display RAM is not known yet.
+            dirty: u16::MAX,
+        }
+    }
+
+    /// The display RAM image.
+    pub fn display_buffer(&self) -> &[DisplayData; ROWS_SIZE] {
+        &self.buffer
+    }
+
+    /// Turn all LEDs off.
+    pub fn clear_display_buffer(&mut self) {
+        for row in 0..ROWS_SIZE {
+            self.set_row(row, DisplayData::empty());
+        }
+    }
+
+    /// Whether any row or the blinking changed since the last flush.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty != 0 || self.blink_changed
+    }
+
+    /// Mark all rows as changed, so the next flush sends the whole RAM image.
+    /// Useful when the display RAM may have been lost, like after a reset of the display.
+    pub fn invalidate(&mut self) {
+        self.dirty = u16::MAX;
+    }
+
+    /// Send the rows changed since the last flush to the display.
+    ///
+    /// Each run of changed rows is sent as one RAM write, the start address followed by
+    /// the row values. A run bridges single unchanged rows, as the digits are on every other
+    /// row and sending one more row costs less than a new write. If the blinking changed, the display setup is sent
+    /// after the rows, turning the display on blinking or steady.
+    /// Nothing is sent if nothing changed.
+    /// On error the rows and the blinking not sent yet stay marked as changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `i2c` - The I2C bus the display is on, only borrowed during the flush.
+    /// * `address` - The I2C address of the display.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ht16k33::i2c_mock::I2cMock;
+    /// use adafruit_7segment::{Frame, Index, SevenSegment};
+    ///
+    /// // Create an I2C device.
+    /// let mut i2c = I2cMock::new();
+    ///
+    /// // The I2C device address.
+    /// const DISP_I2C_ADDR: u8 = 112;
+    ///
+    /// let mut frame = Frame::new();
+    /// frame.update_buffer_with_digit(Index::One, 1);
+    /// // Sends the whole RAM image, the content of the display is not known yet.
+    /// frame.flush(&mut i2c, DISP_I2C_ADDR).expect("Could not write the display RAM!");
+    ///
+    /// frame.update_buffer_with_digit(Index::Four, 4);
+    /// // Only sends the row of the fourth digit.
+    /// frame.flush(&mut i2c, DISP_I2C_ADDR).expect("Could not write the display RAM!");
+    /// ```
+    pub fn flush<I2C, E>(&mut self, i2c: &mut I2C, address: u8) -> Result<(), E>
+    where
+        I2C: Write<Error = E>,
+    {
+        let mut row = 0;
+        while row < ROWS_SIZE {
+            if self.dirty & (1 << row) == 0 {
+                row += 1;
+                continue;
+            }
+            let start = row;
+            row += 1;
+            while row < ROWS_SIZE {
+                if self.is_row_dirty(row) {
+                    row += 1;
+                } else if self.is_row_dirty(row + 1) {
+                    row += 2;
+                } else {
+                    break;
+                }
+            }
+            let mut write_buffer = [0u8; ROWS_SIZE + 1];
+            write_buffer[0] = DisplayDataAddress::ROW_0.bits() + start as u8;
+            for (value, data) in write_buffer[1..].iter_mut().zip(&self.buffer[start..row]) {
+                *value = data.bits();
+            }
+            i2c.write(address, &write_buffer[..row - start + 1])?;
+            for sent in start..row {
+                self.dirty &= !(1 << sent);
+            }
+        }
+        if self.blink_changed {
+            i2c.write(address, &[(Display::COMMAND | self.display()).bits()])?;
+            self.blink_changed = false;
+        }
+        Ok(())
+    }
+
+    /// Turn on the oscillator and the display, blinking if requested by the frame,
+    /// and set the dimming. Like `flush` the bus is only borrowed for the call.
+    ///
+    /// # Arguments
+    ///
+    /// * `i2c` - The I2C bus the display is on, only borrowed during the setup.
+    /// * `address` - The I2C address of the display.
+    /// * `dimming` - The dimming level to set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ht16k33::i2c_mock::I2cMock;
+    /// use ht16k33::Dimming;
+    /// use adafruit_7segment::Frame;
+    ///
+    /// // Create an I2C device.
+    /// let mut i2c = I2cMock::new();
+    ///
+    /// // The I2C device address.
+    /// const DISP_I2C_ADDR: u8 = 112;
+    ///
+    /// let mut frame = Frame::new();
+    /// frame.setup(&mut i2c, DISP_I2C_ADDR, Dimming::BRIGHTNESS_MAX).expect("Could not turn on the display!");
+    /// frame.flush(&mut i2c, DISP_I2C_ADDR).expect("Could not write the display RAM!");
+    /// ```
+    pub fn setup<I2C, E>(&mut self, i2c: &mut I2C, address: u8, dimming: Dimming) -> Result<(), E>
+    where
+        I2C: Write<Error = E>,
+    {
+        i2c.write(address, &[(Oscillator::COMMAND | Oscillator::ON).bits()])?;
+        i2c.write(address, &[(Dimming::COMMAND | dimming).bits()])?;
+        i2c.write(address, &[(Display::COMMAND | self.display()).bits()])?;
+        self.blink_changed = false;
+        Ok(())
+    }
+
+    /// Set the overflow policy used by numeric methods when the format does not set one.
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.overflow = overflow;
+    }
+
+    /// Whether the display should blink, as requested by `Overflow::ClampBlink`.
+    pub fn blink(&self) -> bool {
+        self.blink
+    }
+
+    /// Copy the rows and the blink state of another frame, marking what differs.
+    pub(crate) fn copy_from(&mut self, other: &Frame) {
+        for (row, &value) in other.buffer.iter().enumerate() {
+            self.set_row(row, value);
+        }
+        self.request_blink(other.blink);
+    }
+
+    /// Copy the `bits` of a row from another frame, marking the row if it differs.
+    #[cfg(feature = "critical-section")]
+    pub(crate) fn copy_bits_from(&mut self, other: &Frame, row: usize, bits: u8) {
+        let bits = DisplayData::from_bits_truncate(bits);
+        self.set_row(row, (self.buffer[row] - bits) | (other.buffer[row] & bits));
+    }
+
+    /// The rows, one bit per row, and whether the blinking changed since the last flush,
+    /// no longer marking them.
+    #[cfg(feature = "critical-section")]
+    pub(crate) fn take_dirty(&mut self) -> (u16, bool) {
+        let rows = core::mem::replace(&mut self.dirty, 0);
+        (rows, core::mem::replace(&mut self.blink_changed, false))
+    }
+
+    /// Mark rows, one bit per row, and the blinking as changed.
+    #[cfg(feature = "critical-section")]
+    pub(crate) fn mark_dirty(&mut self, rows: u16, blink: bool) {
+        self.dirty |= rows;
+        self.blink_changed |= blink;
+    }
+
+    /// Set whether the display should blink, marking it as changed if it differs.
+    pub(crate) fn request_blink(&mut self, blink: bool) {
+        if self.blink != blink {
+            self.blink = blink;
+            self.blink_changed = true;
+        }
+    }
+
+    fn display(&self) -> Display {
+        if self.blink {
+            Display::ONE_HZ
+        } else {
+            Display::ON
+        }
+    }
+
+    fn is_row_dirty(&self, row: usize) -> bool {
+        row < ROWS_SIZE && self.dirty & (1 << row) != 0
+    }
+
+    fn set_row(&mut self, row: usize, value: DisplayData) {
+        if self.buffer[row] != value {
+            self.buffer[row] = value;
+            self.dirty |= 1 << row;
+        }
+    }
+
+    fn set_bit(&mut self, index: u8, bit: u8, on: bool) {
+        let row = usize::from(index * 2 + bit / 8);
+        let common = DisplayData::from_bits_truncate(1 << (bit % 8));
+        let mut value = self.buffer[row];
+        value.set(common, on);
+        self.set_row(row, value);
+    }
+
+    fn update_bits(&mut self, index: Index, bits: u8) {
+        let pos = digit_position(index);
+        let row = usize::from(pos * 2);
+        self.set_row(row, DisplayData::from_bits_truncate(bits));
+    }
+}
+
+impl SevenSegment<Infallible> for Frame {
+    /// Update the buffer with a hex digit value (0x00 to 0x0F) at the specified index.
+    fn update_buffer_with_digit(&mut self, index: Index, value: u8) {
+        let value = value as usize;
+        assert!(value < HEX_NUMBER_FONT_TABLE.len());
+        self.update_bits(index, HEX_NUMBER_FONT_TABLE[value]);
+    }
+
+    /// Update the buffer to turn the . on or off at the specified index.
+    fn update_buffer_with_dot(&mut self, index: Index, dot_on: bool) {
+        self.set_bit(digit_position(index), DOT_BIT, dot_on);
+    }
+
+    /// Update the buffer to turn the : on or off.
+    fn update_buffer_with_colon(&mut self, colon_on: bool) {
+        // The colon is at address 2.
+        self.set_bit(2, COLON_BIT, colon_on);
+    }
+
+    /// Update the buffer with an ascii character at the specified index.
+    fn update_buffer_with_char(&mut self, index: Index, value: AsciiChar) -> Result<(), Error> {
+        self.update_bits(index, char_bits(value)?);
+        Ok(())
+    }
+
+    /// Update the buffer with raw segments at the specified index, bit 0 is segment A and bit 7 the dot.
+    fn update_buffer_with_segments(&mut self, index: Index, segments: u8) {
+        self.update_bits(index, segments);
+    }
+
+    /// The overflow policy set with [`Frame::set_overflow`].
+    fn overflow(&self) -> Overflow {
+        self.overflow
+    }
+
+    /// Record that the display should blink, sent by the next flush, see [`Frame::blink`].
+    fn set_blink(&mut self, blink: bool) -> Result<(), Error> {
+        self.request_blink(blink);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use self::std::cell::RefCell;
+    use self::std::vec;
+    use super::*;
+    use crate::test_support::SharedBus;
+    use crate::FloatFormat;
+    use embedded_hal::blocking::i2c::WriteRead;
+    use embedded_hal_mock::i2c::{Mock as I2cMock, Transaction};
+    use ht16k33::HT16K33;
+
+    const ADDRESS: u8 = 0;
+
+    #[test]
+    fn matches_ht16k33() {
+        let mut frame = Frame::new();
+
+        assert!(frame
+            .update_buffer_with_float(Index::One, -9.9, 1, 10)
+            .is_ok());
+        frame.update_buffer_with_colon(true);
+        frame.update_buffer_with_dot(Index::Four, true);
+        assert_eq!(frame.display_buffer()[0].bits(), 0b0000_0000);
+        assert_eq!(frame.display_buffer()[2].bits(), 0b0100_0000);
+        assert_eq!(frame.display_buffer()[4].bits(), 0b0000_0010);
+        assert_eq!(frame.display_buffer()[6].bits(), 0b1110_1111);
+        assert_eq!(frame.display_buffer()[8].bits(), 0b1110_1111);
+
+        frame.clear_display_buffer();
+        for row in frame.display_buffer().iter() {
+            assert_eq!(row.bits(), 0b0000_0000);
+        }
+    }
+
+    #[test]
+    fn display_wide_overflow() {
+        let mut frame = Frame::new();
+
+        assert!(frame
+            .update_buffer_with_fixed(Index::One, 12345, 0)
+            .is_err());
+
+        frame.set_overflow(Overflow::ClampBlink);
+        assert!(frame.update_buffer_with_fixed(Index::One, 12345, 0).is_ok());
+        assert!(frame.blink());
+        assert_eq!(frame.display_buffer()[0].bits(), 0b0110_1111);
+        assert_eq!(frame.display_buffer()[8].bits(), 0b0110_1111);
+
+        // A format overrides the display-wide policy.
+        let format = FloatFormat::new(0, 10).overflow(Overflow::Error);
+        assert!(frame
+            .update_buffer_with_formatted_fixed(Index::One, 12345, 0, &format)
+            .is_err());
+        assert!(frame.blink());
+
+        assert!(frame.update_buffer_with_fixed(Index::One, 1234, 0).is_ok());
+        assert!(!frame.blink());
+    }
+
+    #[test]
+    fn flush_changed_rows() {
+        let mut all_rows = vec![0x00, 0b0000_0110];
+        all_rows.extend([0; ROWS_SIZE - 1].iter().cloned());
+        let expectations = [
+            Transaction::write(ADDRESS, all_rows),
+            Transaction::write(ADDRESS, vec![0x08, 0b0110_0110]),
+            Transaction::write(ADDRESS, vec![0x04, 0b0000_0010]),
+            Transaction::write(ADDRESS, vec![0x08, 0b1110_0110]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut frame = Frame::new();
+        frame.update_buffer_with_digit(Index::One, 1);
+        assert!(frame.flush(&mut i2c, ADDRESS).is_ok());
+        assert!(!frame.is_dirty());
+
+        // Writing the same value does not mark the row.
+        frame.update_buffer_with_digit(Index::One, 1);
+        frame.update_buffer_with_digit(Index::Four, 4);
+        assert!(frame.flush(&mut i2c, ADDRESS).is_ok());
+        assert!(frame.flush(&mut i2c, ADDRESS).is_ok());
+
+        // Rows 4 and 8 are not contiguous.
+        frame.update_buffer_with_colon(true);
+        frame.update_buffer_with_dot(Index::Four, true);
+        assert!(frame.flush(&mut i2c, ADDRESS).is_ok());
+
+        i2c.done();
+    }
+
+    #[test]
+    fn flush_merged_rows() {
+        let mut all_rows = vec![0x00];
+        all_rows.extend([0; ROWS_SIZE].iter().cloned());
+        let expectations = [
+            Transaction::write(ADDRESS, all_rows.clone()),
+            // Rows 0 to 8, the unchanged odd rows between the digits and the colon included.
+            Transaction::write(
+                ADDRESS,
+                vec![
+                    0x00,
+                    0b0110_1101,
+                    0,
+                    0b0111_1001,
+                    0,
+                    0b0000_0010,
+                    0,
+                    0b0111_0111,
+                    0,
+                    0b0111_0001,
+                ],
+            ),
+            Transaction::write(ADDRESS, vec![0x02, 0b0100_1111, 0, 0]),
+            Transaction::write(ADDRESS, all_rows),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut frame = Frame::new();
+        assert!(frame.flush(&mut i2c, ADDRESS).is_ok());
+
+        frame.update_buffer_with_hex(0x5EAF);
+        frame.update_buffer_with_colon(true);
+        assert!(frame.flush(&mut i2c, ADDRESS).is_ok());
+
+        // A gap of one row is bridged.
+        frame.update_buffer_with_digit(Index::Two, 3);
+        frame.update_buffer_with_colon(false);
+        assert!(frame.flush(&mut i2c, ADDRESS).is_ok());
+
+        frame.clear_display_buffer();
+        frame.invalidate();
+        assert!(frame.flush(&mut i2c, ADDRESS).is_ok());
+
+        i2c.done();
+    }
+
+    #[test]
+    fn flush_blink() {
+        let mut all_rows = vec![0x00];
+        all_rows.extend([0; ROWS_SIZE].iter().cloned());
+        let expectations = [
+            Transaction::write(ADDRESS, vec![0x21]),
+            Transaction::write(ADDRESS, vec![0xEF]),
+            Transaction::write(ADDRESS, vec![0x81]),
+            Transaction::write(ADDRESS, all_rows),
+            Transaction::write(ADDRESS, vec![0x08, 0b0110_1111]),
+            Transaction::write(ADDRESS, vec![0x85]),
+            Transaction::write(ADDRESS, vec![0x08, 0b0110_1101]),
+            Transaction::write(ADDRESS, vec![0x81]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+
+        let mut frame = Frame::new();
+        frame.set_overflow(Overflow::ClampBlink);
+        assert!(frame
+            .setup(&mut i2c, ADDRESS, Dimming::BRIGHTNESS_MAX)
+            .is_ok());
+        assert!(frame.flush(&mut i2c, ADDRESS).is_ok());
+
+        // The blinking is sent after the rows, and only when it changes.
+        assert!(frame.update_buffer_with_fixed(Index::Four, 12, 0).is_ok());
+        assert!(frame.flush(&mut i2c, ADDRESS).is_ok());
+        assert!(frame.update_buffer_with_fixed(Index::Four, 34, 0).is_ok());
+        assert!(!frame.is_dirty());
+        assert!(frame.update_buffer_with_fixed(Index::Four, 5, 0).is_ok());
+        assert!(frame.flush(&mut i2c, ADDRESS).is_ok());
+        assert!(!frame.is_dirty());
+
+        i2c.done();
+    }
+
+    #[test]
+    fn shared_bus() {
+        const SENSOR: u8 = 0x40;
+        let mut all_rows = vec![0x00];
+        all_rows.extend([0; ROWS_SIZE].iter().cloned());
+        let expectations = [
+            // The display owning a proxy.
+            Transaction::write(ADDRESS, vec![0x21]),
+            Transaction::write_read(SENSOR, vec![0xE3], vec![0x66, 0x4C]),
+            Transaction::write(ADDRESS, vec![0x81]),
+            // A frame borrowing a proxy only to set up and flush.
+            Transaction::write(ADDRESS, vec![0x21]),
+            Transaction::write(ADDRESS, vec![0xE7]),
+            Transaction::write(ADDRESS, vec![0x85]),
+            Transaction::write(ADDRESS, all_rows),
+            Transaction::write_read(SENSOR, vec![0xE3], vec![0x66, 0x4C]),
+            Transaction::write(ADDRESS, vec![0x08, 0b0110_1101]),
+        ];
+        let bus = RefCell::new(I2cMock::new(&expectations));
+        let mut sensor = SharedBus(&bus);
+        let mut reading = [0; 2];
+
+        let mut ht16k33 = HT16K33::new(SharedBus(&bus), ADDRESS);
+        ht16k33.set_oscillator(Oscillator::ON).unwrap();
+        sensor.write_read(SENSOR, &[0xE3], &mut reading).unwrap();
+        ht16k33.set_display(Display::ON).unwrap();
+
+        let mut frame = Frame::new();
+        frame.set_overflow(Overflow::ClampBlink);
+        assert!(frame
+            .update_buffer_with_float(Index::One, 12345., 0, 10)
+            .is_ok());
+        frame.clear_display_buffer();
+        frame
+            .setup(&mut SharedBus(&bus), ADDRESS, Dimming::BRIGHTNESS_8_16)
+            .unwrap();
+        frame.flush(&mut SharedBus(&bus), ADDRESS).unwrap();
+        sensor.write_read(SENSOR, &[0xE3], &mut reading).unwrap();
+        frame.update_buffer_with_digit(Index::Four, 5);
+        frame.flush(&mut SharedBus(&bus), ADDRESS).unwrap();
+
+        bus.into_inner().done();
+    }
+}
@@ -14,10 +14,36 @@
 //!
 //! ## Features
 //! * Sending a `u8` to one of the 4 segments. Limited to 0x00 to 0x0F.
-//! * Sending an `AsciiChar` to one of the 4 segments. Limited to ascii hex chars and - sign.
+//! * Sending an `AsciiChar` to one of the 4 segments. Limited to ascii alphanumeric chars, space and - sign.
+//! * Sending raw segments to one of the 4 segments.
+//! * Showing a `u16` as zero-padded hex, a `u8` as hex with an `h` suffix, or a `u16` as a binary pattern of 4 bits per digit.
 //! * Setting or unsetting the dot associated with one of the 4 segments.
 //! * Setting or unsetting the colon.
-//! * Formatting a `f32` to 1 to 4 segments
+//! * Setting the brightness from 0-100% or 0-255 through a gamma curve with `Brightness`, and fading in or out over ticks with `Fade`.
+//! * Automatic brightness from ambient light readings with `AutoBrightness`, smoothed and with hysteresis, through a configurable lux curve.
+//! * Standby with `sleep` and `wake`, turning the oscillator off and sending the buffer again on wake, and an `IdleTimeout` policy blanking the display after inactivity.
+//! * Formatting a `f32` or `f64` to 1 to 4 segments, with exact rounding and `nAn`/`inF` for special values.
+//! * Automatic precision for floats, using all free digits and optionally trimming trailing zeros.
+//! * Formatting fixed-point integers, like centidegrees, using integer arithmetic only.
+//! * Scientific (`1.2E5`) or engineering (`12.3k`) notation for numbers that do not fit as plain digits.
+//! * Overflow policy for numbers that do not fit: error, clamp to `9999`, dashes, `OFL`, `Err` or clamp and blink. Set per format or display-wide on a `Frame`.
+//! * Measurements followed by a unit, `°`, `°C`, `°F`, `%`, `V`, `A`, `H` or `P`, with the precision reduced to fit.
+//! * Formatting into a `Region` of adjacent digits, so values shown side by side do not blank each other.
+//! * Drawing on a `Frame`, a display buffer that does not own the I2C bus.
+//! * Flushing a `Frame` with minimal RAM writes, only the rows changed since the last flush are sent.
+//! * Sharing the I2C bus with other drivers through bus proxies, or drawing on a `Frame` and borrowing the bus only to set up and flush it.
+//! * Horizontal `BarGraph` level meter using the vertical segment pairs as half steps, optionally with the dots and colon.
+//! * Stopwatch and countdown `Timer` widget, switching between `SS.hh`, `MM:SS` and `HH:MM` as time grows, with a blinking colon.
+//! * Double buffering with `DoubleBuffer`, drawing on a back buffer and committing it in one step so half drawn frames are never sent. Or committing a `Frame` to a `FrontBuffer` flushed from an interrupt handler, with the `critical-section` feature.
+//! * Sharing the display between tasks with `SharedDisplay`, a `critical_section::Mutex` frame where each task owns some digits or the colon, merged on flush. Needs the `critical-section` feature.
+//! * Verifying the display RAM by reading it back, optionally initializing the display again and re-sending the buffer on mismatch.
+//! * Decoding a display buffer back into digit segments, dots and colon with `DecodedBuffer`, or into text like `-3.14` by formatting it.
+//! * Rendering a display buffer as ASCII or Unicode art with `render`, for tests and logs. Needs the `std` feature.
+//! * Exporting a display buffer as an SVG image with `svg`, with configurable color, ghosting of off segments, digit size and 0.56" or 1.2" board. Needs the `std` feature.
+//! * Testing without hardware against `Simulator`, a simulated HT16K33 on the I2C bus that interprets the commands sent to it.
+//! * A command-line tool for Linux with the optional `cli` feature, showing text, the time or scrolling text, turning the display on or off, and setting brightness and blinking.
+//! * Using `write!` on the display through `DisplayWriter`, a `core::fmt::Write` implementation.
+//! * Using `uwrite!` on the display through `DisplayWriter` with the optional `ufmt` feature, for small targets.
 //!
 //! # Usage
 //!
@@ -137,6 +163,45 @@
 //! // call write_display_buffer to actually send it to the display
 //! ht16k33.write_display_buffer().unwrap()
 //!```
+//! ## Sharing the I2C bus
+//!
+//! `HT16K33::new` takes ownership of the I2C bus. On boards where the bus is shared with sensors,
+//! give it a proxy from a bus manager like the [`shared-bus` crate](https://crates.io/crates/shared-bus), or any other type implementing
+//! the blocking `Write` and `WriteRead` traits of `embedded-hal` 0.2.
+//! `embedded-hal-bus` implements the `embedded-hal` 1.0 traits, which this crate does not use yet.
+//!
+//! Alternatively draw on a `Frame`, which does not own the bus, and only borrow the bus to set up and flush it.
+//!
+//!```
+//! # // Just enough of the `shared-bus` API to compile and run the example.
+//! # mod shared_bus {
+//! #     use ht16k33::i2c_mock::I2cMock;
+//! #     pub struct BusManagerSimple;
+//! #     impl BusManagerSimple {
+//! #         pub fn new(_: I2cMock) -> Self { BusManagerSimple }
+//! #         pub fn acquire_i2c(&self) -> I2cMock { I2cMock::new() }
+//! #     }
+//! # }
+//! # struct Sensor;
+//! # impl Sensor { fn new<I2C>(_: I2C) -> Self { Sensor } }
+//! # let i2c = ht16k33::i2c_mock::I2cMock::new();
+//! # const DISP_I2C_ADDR: u8 = 112;
+//! use ht16k33::{Dimming, HT16K33};
+//! use adafruit_7segment::{Frame, Index, SevenSegment};
+//!
+//! let bus = shared_bus::BusManagerSimple::new(i2c);
+//!
+//! // The display owning a proxy, next to a sensor owning another.
+//! let mut ht16k33 = HT16K33::new(bus.acquire_i2c(), DISP_I2C_ADDR);
+//! let mut sensor = Sensor::new(bus.acquire_i2c());
+//!
+//! // Or a frame, borrowing a proxy only while talking to the display.
+//! let mut i2c = bus.acquire_i2c();
+//! let mut frame = Frame::new();
+//! frame.setup(&mut i2c, DISP_I2C_ADDR, Dimming::BRIGHTNESS_MAX).unwrap();
+//! frame.update_buffer_with_digit(Index::One, 1);
+//! frame.flush(&mut i2c, DISP_I2C_ADDR).unwrap();
+//!```
 //! ## Performance warning
 //!
 //! Due to the api of the ht16k33 crate the display buffer is not directly accessible so each LED that makes up the character is updated sequentially. The way the hardware on this backpack is set up allows a character to be updated by setting a single 16-bit value in the buffer. Iterating over each bit of the 16 every update is clearly not optimal but it's sufficiently fast for my current usage. If the ht16k33 crate is updated to grant mut access to the buffer this can be improved.
@@ -148,6 +213,48 @@
 
 mod fonts;
 use fonts::*;
+mod auto_brightness;
+pub use auto_brightness::AutoBrightness;
+mod bar_graph;
+pub use bar_graph::BarGraph;
+mod brightness;
+pub use brightness::{Brightness, BrightnessControl, Fade};
+mod decode;
+pub use decode::DecodedBuffer;
+mod double_buffer;
+pub use double_buffer::DoubleBuffer;
+#[cfg(feature = "critical-section")]
+pub use double_buffer::FrontBuffer;
+mod frame;
+pub use frame::Frame;
+mod number;
+pub use number::{FloatFormat, Notation, Overflow, Rounding, Unit};
+mod power;
+pub use power::{IdleEvent, IdleTimeout, PowerManagement};
+mod region;
+pub use region::Region;
+#[cfg(feature = "std")]
+mod render;
+#[cfg(feature = "std")]
+pub use render::{render, Style};
+#[cfg(feature = "critical-section")]
+mod shared;
+#[cfg(feature = "critical-section")]
+pub use shared::{DisplayHandle, Parts, SharedDisplay};
+mod simulator;
+pub use simulator::{Simulator, SimulatorError};
+#[cfg(feature = "std")]
+mod svg;
+#[cfg(feature = "std")]
+pub use svg::{svg, Board, SvgOptions};
+mod timer;
+pub use timer::{Timer, TimerStatus};
+mod verify;
+pub use verify::{Verification, Verify};
+mod writer;
+pub use writer::DisplayWriter;
+#[cfg(test)]
+mod test_support;
 
 pub use ascii::{AsciiChar, ToAsciiChar};
 use embedded_hal::blocking::i2c::{Write, WriteRead};
@@ -160,6 +267,10 @@ pub enum Error {
     InsufficientDigits,
     /// Error indicating that the input cannot be displayed.
     NotValidChar,
+    /// Error indicating that a command could not be sent to the display.
+    Bus,
+    /// Error indicating that the display can not blink on request, for `Overflow::ClampBlink`.
+    BlinkUnsupported,
 }
 
 /// Trait enabling using the Adafruit 7-segment LED numeric Backpack.
@@ -172,14 +283,282 @@ pub trait SevenSegment<E> {
     fn update_buffer_with_colon(&mut self, colon_on: bool);
     /// Update the buffer with an ascii character at the specified index.
     fn update_buffer_with_char(&mut self, index: Index, value: AsciiChar) -> Result<(), Error>;
-    /// Update the buffer with a formatted float not starting before the specified index.
+    /// Update the buffer with raw segments at the specified index, bit 0 is segment A and bit 7 the dot.
+    fn update_buffer_with_segments(&mut self, index: Index, segments: u8);
+    /// Update the buffer with a formatted float not starting before the specified index
+    /// The logic for this is based on the adafruit library. Only difference is this allows the start index to be > 0
+    /// and that the value is rounded exactly, half away from zero.
+    ///
+    /// NaN is shown as `nAn` and infinity as `inF` or `-inF`, an upper case I would look like a 1.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Digit index.
+    /// * `value` - float value.
+    /// * `fractional_digits` - Number of fractional digits.
+    /// * `base` - Base to use.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ht16k33::i2c_mock::I2cMock;
+    /// use ht16k33::HT16K33;
+    /// use adafruit_7segment::{SevenSegment, Index};
+    ///
+    /// // Create an I2C device.
+    /// let mut i2c = I2cMock::new();
+    ///
+    /// // The I2C device address.
+    /// const DISP_I2C_ADDR: u8 = 112;
+    ///
+    /// let mut ht16k33 = HT16K33::new(i2c, DISP_I2C_ADDR);
+    ///
+    /// // Write 9.9 from pos 2
+    /// ht16k33.update_buffer_with_float(Index::Two, 9.9, 1, 10);
+    /// ```
     fn update_buffer_with_float(
         &mut self,
         index: Index,
         value: f32,
         fractional_digits: u8,
         base: u8,
-    ) -> Result<(), Error>;
+    ) -> Result<(), Error> {
+        let format = FloatFormat::new(fractional_digits, base);
+        number::update_buffer_with_number(self, index.into(), 4, value.into(), &format)
+    }
+    /// Update the buffer with a formatted double not starting before the specified index
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Digit index.
+    /// * `value` - double value.
+    /// * `fractional_digits` - Number of fractional digits.
+    /// * `base` - Base to use.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ht16k33::i2c_mock::I2cMock;
+    /// use ht16k33::HT16K33;
+    /// use adafruit_7segment::{SevenSegment, Index};
+    ///
+    /// // Create an I2C device.
+    /// let mut i2c = I2cMock::new();
+    ///
+    /// // The I2C device address.
+    /// const DISP_I2C_ADDR: u8 = 112;
+    ///
+    /// let mut ht16k33 = HT16K33::new(i2c, DISP_I2C_ADDR);
+    ///
+    /// // Write 2.67 using the whole display
+    /// ht16k33.update_buffer_with_f64(Index::One, 2.675, 2, 10);
+    /// ```
+    fn update_buffer_with_f64(
+        &mut self,
+        index: Index,
+        value: f64,
+        fractional_digits: u8,
+        base: u8,
+    ) -> Result<(), Error> {
+        let format = FloatFormat::new(fractional_digits, base);
+        number::update_buffer_with_number(self, index.into(), 4, value.into(), &format)
+    }
+    /// Update the buffer with a float formatted as described by `format`, not starting before the specified index
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Digit index.
+    /// * `value` - double value, an `f32` converts without loss.
+    /// * `format` - Formatting options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ht16k33::i2c_mock::I2cMock;
+    /// use ht16k33::HT16K33;
+    /// use adafruit_7segment::{SevenSegment, Index, FloatFormat, Rounding};
+    ///
+    /// // Create an I2C device.
+    /// let mut i2c = I2cMock::new();
+    ///
+    /// // The I2C device address.
+    /// const DISP_I2C_ADDR: u8 = 112;
+    ///
+    /// let mut ht16k33 = HT16K33::new(i2c, DISP_I2C_ADDR);
+    ///
+    /// // Write 0.12 using the whole display
+    /// let format = FloatFormat::new(2, 10).rounding(Rounding::HalfEven);
+    /// ht16k33.update_buffer_with_formatted_float(Index::One, 0.125, &format);
+    /// ```
+    fn update_buffer_with_formatted_float(
+        &mut self,
+        index: Index,
+        value: f64,
+        format: &FloatFormat,
+    ) -> Result<(), Error> {
+        number::update_buffer_with_number(self, index.into(), 4, value.into(), format)
+    }
+    /// Update the buffer with a fixed-point value not starting before the specified index.
+    /// Only integer arithmetic is used, the value shown is `value / 10^scale_digits`
+    /// with `scale_digits` fractional digits. As for floats the number of fractional digits
+    /// is reduced if the value does not fit.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Digit index.
+    /// * `value` - Scaled integer value, e.g. centidegrees.
+    /// * `scale_digits` - Number of decimal digits the value is scaled by.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ht16k33::i2c_mock::I2cMock;
+    /// use ht16k33::HT16K33;
+    /// use adafruit_7segment::{SevenSegment, Index};
+    ///
+    /// // Create an I2C device.
+    /// let mut i2c = I2cMock::new();
+    ///
+    /// // The I2C device address.
+    /// const DISP_I2C_ADDR: u8 = 112;
+    ///
+    /// let mut ht16k33 = HT16K33::new(i2c, DISP_I2C_ADDR);
+    ///
+    /// // Write 23.45 from 2345 centidegrees
+    /// ht16k33.update_buffer_with_fixed(Index::One, 2345, 2);
+    /// ```
+    fn update_buffer_with_fixed(
+        &mut self,
+        index: Index,
+        value: i32,
+        scale_digits: u8,
+    ) -> Result<(), Error> {
+        let format = FloatFormat::new(scale_digits, 10);
+        let value = number::Number::fixed(value, scale_digits);
+        number::update_buffer_with_number(self, index.into(), 4, value, &format)
+    }
+    /// Update the buffer with a fixed-point value formatted as described by `format`,
+    /// not starting before the specified index.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Digit index.
+    /// * `value` - Scaled integer value, e.g. centidegrees.
+    /// * `scale_digits` - Number of decimal digits the value is scaled by.
+    /// * `format` - Formatting options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ht16k33::i2c_mock::I2cMock;
+    /// use ht16k33::HT16K33;
+    /// use adafruit_7segment::{SevenSegment, Index, FloatFormat};
+    ///
+    /// // Create an I2C device.
+    /// let mut i2c = I2cMock::new();
+    ///
+    /// // The I2C device address.
+    /// const DISP_I2C_ADDR: u8 = 112;
+    ///
+    /// let mut ht16k33 = HT16K33::new(i2c, DISP_I2C_ADDR);
+    ///
+    /// // Write 23.5 from 2345 centidegrees
+    /// let format = FloatFormat::new(1, 10);
+    /// ht16k33.update_buffer_with_formatted_fixed(Index::One, 2345, 2, &format);
+    /// ```
+    fn update_buffer_with_formatted_fixed(
+        &mut self,
+        index: Index,
+        value: i32,
+        scale_digits: u8,
+        format: &FloatFormat,
+    ) -> Result<(), Error> {
+        let value = number::Number::fixed(value, scale_digits);
+        number::update_buffer_with_number(self, index.into(), 4, value, format)
+    }
+    /// Update the buffer with a measurement followed by its unit, not starting before the specified index.
+    /// The unit takes up the last digits and the value is shown with as many fractional digits as fit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use adafruit_7segment::{Frame, Index, SevenSegment, Unit};
+    ///
+    /// let mut frame = Frame::new();
+    ///
+    /// // Display reads "23°C".
+    /// frame.update_buffer_with_measurement(Index::One, 23.4, Unit::Celsius).expect("Failed to encode measurement to buffer!");
+    /// ```
+    fn update_buffer_with_measurement(
+        &mut self,
+        index: Index,
+        value: f64,
+        unit: Unit,
+    ) -> Result<(), Error> {
+        let format = FloatFormat::auto(10).unit(unit);
+        self.update_buffer_with_formatted_float(index, value, &format)
+    }
+    /// Update the buffer with a value as four zero-padded hex digits, like `00FF`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use adafruit_7segment::{Frame, SevenSegment};
+    ///
+    /// let mut frame = Frame::new();
+    ///
+    /// // Display reads "BEEF".
+    /// frame.update_buffer_with_hex(0xBEEF);
+    /// ```
+    fn update_buffer_with_hex(&mut self, value: u16) {
+        for i in 0..4 {
+            let shift = 12 - 4 * i;
+            self.update_buffer_with_digit(Index::from(i), ((value >> shift) & 0xF) as u8);
+        }
+    }
+    /// Update the buffer with a byte as two hex digits followed by `h`, like ` 3Fh`.
+    fn update_buffer_with_hex_u8(&mut self, value: u8) {
+        self.update_buffer_with_segments(Index::One, 0);
+        self.update_buffer_with_digit(Index::Two, value >> 4);
+        self.update_buffer_with_digit(Index::Three, value & 0xF);
+        self.update_buffer_with_segments(Index::Four, HEX_SIGN);
+    }
+    /// Update the buffer with a value as 16 bits, 4 bits per digit with the most significant bits first.
+    /// Within a digit the bits are shown on the vertical segments in reading order,
+    /// upper left, upper right, lower left and lower right, a lit segment is a set bit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use adafruit_7segment::{Frame, SevenSegment};
+    ///
+    /// let mut frame = Frame::new();
+    ///
+    /// // The first digit shows 0b1001 on its upper left and lower right segments.
+    /// frame.update_buffer_with_binary(0b1001_0000_0000_1111);
+    /// ```
+    fn update_buffer_with_binary(&mut self, value: u16) {
+        // Segments C, E, B and F, for bit 0 up to bit 3.
+        const BIT_SEGMENTS: [u8; 4] = [0x04, 0x10, 0x02, 0x20];
+        for i in 0..4 {
+            let nibble = (value >> (12 - 4 * i)) & 0xF;
+            let segments = (0..4)
+                .filter(|bit| nibble & (1 << bit) != 0)
+                .fold(0, |segments, bit| segments | BIT_SEGMENTS[bit]);
+            self.update_buffer_with_segments(Index::from(i), segments);
+        }
+    }
+    /// Display-wide overflow policy, used by numeric methods when the format does not set one.
+    fn overflow(&self) -> Overflow {
+        Overflow::Error
+    }
+    /// Request blinking of the whole display, used by `Overflow::ClampBlink`.
+    /// Like the other methods nothing is sent to the device. By default the request can not
+    /// be recorded and `Error::BlinkUnsupported` is returned.
+    fn set_blink(&mut self, blink: bool) -> Result<(), Error> {
+        let _ = blink;
+        Err(Error::BlinkUnsupported)
+    }
 }
 
 /// The index of a segment
@@ -220,10 +599,50 @@ impl From<u8> for Index {
 
 const MINUS_SIGN: u8 = 0x40;
 
+/// Upper case M, drawn as segments A, B, C, E and F so it differs from the lower case m of the font.
+const MEGA_SIGN: u8 = 0x37;
+
+/// Lower case h after a hex byte, segments C, E, F and G.
+const HEX_SIGN: u8 = 0x74;
+
 const DOT_BIT: u8 = 7;
 
 const COLON_BIT: u8 = 1;
 
+/// Segments lit for an ascii character.
+fn char_bits(value: AsciiChar) -> Result<u8, Error> {
+    if value == 'M' {
+        Ok(MEGA_SIGN)
+    } else if value.is_alphanumeric() {
+        let val = if value.is_ascii_digit() {
+            // 0-9 converted to hex value
+            value.as_byte() - b'0'
+        } else {
+            // a-f or A-F converted to hex value
+            0x0A + (value.to_ascii_uppercase().as_byte() - b'A')
+        };
+        let val = val as usize;
+        assert!(val < HEX_NUMBER_FONT_TABLE.len());
+        Ok(HEX_NUMBER_FONT_TABLE[val])
+    } else if value == '-' {
+        Ok(MINUS_SIGN)
+    } else if value == ' ' {
+        Ok(0)
+    } else {
+        Err(Error::NotValidChar)
+    }
+}
+
+/// Position of the digit in the display RAM.
+fn digit_position(index: Index) -> u8 {
+    if index > Index::Two {
+        // Move one step to compensate for colon at pos 2.
+        u8::from(index) + 1u8
+    } else {
+        index.into()
+    }
+}
+
 fn set_bit<I2C, E>(display: &mut HT16K33<I2C>, index: u8, bit: u8, on: bool)
 where
     I2C: Write<Error = E> + WriteRead<Error = E>,
@@ -239,13 +658,7 @@ fn update_bits<I2C, E>(display: &mut HT16K33<I2C>, index: Index, bits: u8)
 where
     I2C: Write<Error = E> + WriteRead<Error = E>,
 {
-    let pos: u8;
-    if index > Index::Two {
-        // Move one step to compensate for colon at pos 2.
-        pos = u8::from(index) + 1u8;
-    } else {
-        pos = index.into();
-    }
+    let pos = digit_position(index);
     for i in 0..8 {
         let on = ((bits >> i) & 1) == 1;
         set_bit(display, pos, i, on);
@@ -312,14 +725,7 @@ where
     /// ht16k33.update_buffer_with_dot(Index::One, true);
     /// ```
     fn update_buffer_with_dot(&mut self, index: Index, dot_on: bool) {
-        let pos: u8;
-        if index > Index::Two {
-            // Move one step to compensate for colon at pos 2.
-            pos = u8::from(index) + 1u8;
-        } else {
-            pos = index.into();
-        }
-        set_bit(self, pos, DOT_BIT, dot_on);
+        set_bit(self, digit_position(index), DOT_BIT, dot_on);
     }
 
     /// Update the buffer to turn the : on or off.
@@ -351,6 +757,7 @@ where
     }
 
     /// Update the buffer with an ascii character at the specified index.
+    /// A space blanks the digit.
     /// # Arguments
     ///
     /// * `index` - Digit index.
@@ -375,37 +782,16 @@ where
     /// ht16k33.update_buffer_with_char(Index::One, AsciiChar::new('c')).expect("Failed to encode char to buffer!");
     /// ```
     fn update_buffer_with_char(&mut self, index: Index, value: AsciiChar) -> Result<(), Error> {
-        if value.is_alphanumeric() {
-            let val: u8;
-            if value.is_ascii_digit() {
-                // 0-9 converted to hex value
-                val = value.as_byte() - b'0';
-            } else {
-                // a-f or A-F converted to hex value
-                val = 0x0A + (value.to_ascii_uppercase().as_byte() - b'A');
-            }
-            let val = val as usize;
-            assert!(val < HEX_NUMBER_FONT_TABLE.len());
-            let bits = HEX_NUMBER_FONT_TABLE[val];
-            update_bits(self, index, bits);
-        } else if value == '-' {
-            update_bits(self, index, MINUS_SIGN);
-        } else {
-            return Err(Error::NotValidChar);
-        }
-
+        update_bits(self, index, char_bits(value)?);
         Ok(())
     }
 
-    /// Update the buffer with a formatted float not starting before the specified index
-    /// The logic for this is copied mostly from from the adafruit library. Only difference is this allows the start index to be > 0
-    ///
+    /// Update the buffer with raw segments at the specified index.
+    /// Bit 0 to 6 are segment A to G and bit 7 is the dot.
     /// # Arguments
     ///
     /// * `index` - Digit index.
-    /// * `value` - float value.
-    /// * `fractional_digits` - Number of fractional digits.
-    /// * `base` - Base to use.
+    /// * `segments` - Segments to turn on, the others are turned off.
     ///
     /// # Examples
     ///
@@ -422,96 +808,11 @@ where
     ///
     /// let mut ht16k33 = HT16K33::new(i2c, DISP_I2C_ADDR);
     ///
-    /// // Write 9.9 from pos 2
-    /// ht16k33.update_buffer_with_float(Index::Two, 9.9, 1, 10);
+    /// // Set first digit to a degree sign.
+    /// ht16k33.update_buffer_with_segments(Index::One, 0b0110_0011);
     /// ```
-    fn update_buffer_with_float(
-        &mut self,
-        index: Index,
-        mut value: f32,
-        mut fractional_digits: u8,
-        base: u8,
-    ) -> Result<(), Error> {
-        let index = u8::from(index);
-
-        // Available digits on display
-        let mut numeric_digits = 4 - index;
-
-        let is_negative = if value < 0. {
-            // The sign will take up one digit
-            numeric_digits -= 1;
-            // Flip the sign to do the rest of the formatting
-            value *= -1.;
-            true
-        } else {
-            false
-        };
-
-        let base = base as u32;
-        let basef = base as f32;
-
-        // Work out the multiplier needed to get all fraction digits into an integer
-        let mut to_int_factor = base.pow(fractional_digits as u32) as f32;
-
-        // Get an integer containing digits to be displayed
-        let mut display_number = ((value * to_int_factor) + 0.5) as u32;
-
-        // Calculate the upper bound given the number of digits available
-        let too_big = base.pow(numeric_digits as u32);
-
-        // If the number is too large, reduce fractional digits
-        while display_number >= too_big {
-            fractional_digits -= 1;
-            to_int_factor /= basef;
-            display_number = ((value * to_int_factor) + 0.5) as u32;
-        }
-
-        // Did we lose the decimal?
-        if to_int_factor < 1. {
-            return Err(Error::InsufficientDigits);
-        }
-
-        // Digit we're working on, less the start position
-        let mut display_pos = (3 - index) as i8;
-
-        if display_number == 0 {
-            // Write out the 0
-            self.update_buffer_with_digit((index + (display_pos as u8)).into(), 0);
-            // Move the current pos along
-            display_pos -= 1;
-        } else {
-            let mut i = 0;
-            while display_number != 0 || i <= fractional_digits {
-                let digit_index: Index = (index + (display_pos as u8)).into();
-                // Write out the current digit
-                self.update_buffer_with_digit(digit_index, (display_number % base) as u8);
-                // Add the decimal if necessary
-                if fractional_digits != 0 && i == fractional_digits {
-                    self.update_buffer_with_dot(digit_index, true);
-                }
-                // Move the current pos along
-                display_pos -= 1;
-                // Move the number along
-                display_number /= base;
-                i += 1;
-            }
-        }
-
-        if is_negative {
-            // Add the minus sign
-            update_bits(self, (index + (display_pos as u8)).into(), MINUS_SIGN);
-            // Move the current pos along
-            display_pos -= 1;
-        }
-
-        // Clear any remaining segments
-        while display_pos >= 0 {
-            update_bits(self, (index + (display_pos as u8)).into(), 0);
-            // Move the current pos along
-            display_pos -= 1;
-        }
-
-        Ok(())
+    fn update_buffer_with_segments(&mut self, index: Index, segments: u8) {
+        update_bits(self, index, segments);
     }
 }
 
@@ -522,6 +823,7 @@ mod tests {
 
     use self::hal::i2c::Mock as I2cMock;
     use super::*;
+    use ht16k33::Display;
 
     const ADDRESS: u8 = 0;
 
@@ -681,6 +983,17 @@ mod tests {
             .is_ok());
         assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0100_0000);
 
+        // Write a space
+        assert!(ht16k33
+            .update_buffer_with_char(Index::One, AsciiChar::new(' '))
+            .is_ok());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0000_0000);
+
+        // Write an _
+        assert!(ht16k33
+            .update_buffer_with_char(Index::One, AsciiChar::new('_'))
+            .is_err());
+
         i2c = ht16k33.destroy();
         i2c.done();
     }
@@ -756,4 +1069,283 @@ mod tests {
         i2c = ht16k33.destroy();
         i2c.done();
     }
+
+    #[test]
+    fn update_buffer_with_float_special_values() {
+        let expectations = [];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut ht16k33 = HT16K33::new(i2c, ADDRESS);
+
+        assert!(ht16k33
+            .update_buffer_with_float(Index::One, f32::NAN, 2, 10)
+            .is_ok());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0000_0000);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b0101_0100);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b0111_0111);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0101_0100);
+
+        assert!(ht16k33
+            .update_buffer_with_float(Index::One, f32::NEG_INFINITY, 2, 10)
+            .is_ok());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0100_0000);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b0000_0100);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b0101_0100);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0111_0001);
+
+        // Not enough digits for the sign
+        assert!(ht16k33
+            .update_buffer_with_float(Index::Two, f32::NEG_INFINITY, 2, 10)
+            .is_err());
+        assert!(ht16k33
+            .update_buffer_with_float(Index::Four, -1., 2, 10)
+            .is_err());
+
+        // Far outside of the u32 range
+        assert!(ht16k33
+            .update_buffer_with_f64(Index::One, 1e20, 2, 10)
+            .is_err());
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0111_0001);
+
+        i2c = ht16k33.destroy();
+        i2c.done();
+    }
+
+    #[test]
+    fn update_buffer_with_float_rounding() {
+        let expectations = [];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut ht16k33 = HT16K33::new(i2c, ADDRESS);
+
+        // 2.675 is stored as 2.67499...
+        assert!(ht16k33
+            .update_buffer_with_f64(Index::One, 2.675, 2, 10)
+            .is_ok());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0000_0000);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b1101_1011);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b0111_1101);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0000_0111);
+
+        assert!(ht16k33
+            .update_buffer_with_float(Index::One, -0.125, 2, 10)
+            .is_ok());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0100_0000);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b1011_1111);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b0000_0110);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0100_1111);
+
+        let format = FloatFormat::new(2, 10).rounding(Rounding::HalfEven);
+        assert!(ht16k33
+            .update_buffer_with_formatted_float(Index::One, -0.125, &format)
+            .is_ok());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0100_0000);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b1011_1111);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b0000_0110);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0101_1011);
+
+        // Rounding up to the next power of the base drops a fractional digit
+        assert!(ht16k33
+            .update_buffer_with_float(Index::One, 9.9996, 3, 10)
+            .is_ok());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0000_0110);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b1011_1111);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b0011_1111);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0011_1111);
+
+        i2c = ht16k33.destroy();
+        i2c.done();
+    }
+
+    #[test]
+    fn update_buffer_with_float_auto_precision() {
+        let expectations = [];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut ht16k33 = HT16K33::new(i2c, ADDRESS);
+
+        let format = FloatFormat::auto(10);
+        assert!(ht16k33
+            .update_buffer_with_formatted_float(Index::One, 1.234, &format)
+            .is_ok());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b1000_0110);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b0101_1011);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b0100_1111);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0110_0110);
+
+        assert!(ht16k33
+            .update_buffer_with_formatted_float(Index::One, 123.4, &format)
+            .is_ok());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0000_0110);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b0101_1011);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b1100_1111);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0110_0110);
+
+        assert!(ht16k33
+            .update_buffer_with_formatted_float(Index::One, 1234., &format)
+            .is_ok());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0000_0110);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b0101_1011);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b0100_1111);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0110_0110);
+
+        assert!(ht16k33
+            .update_buffer_with_formatted_float(Index::Two, -1.234, &format)
+            .is_ok());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0000_0110);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b0100_0000);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b1000_0110);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0101_1011);
+
+        let format = FloatFormat::auto(10).trim_trailing_zeros(true);
+        assert!(ht16k33
+            .update_buffer_with_formatted_float(Index::One, 1.5, &format)
+            .is_ok());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0000_0000);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b0000_0000);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b1000_0110);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0110_1101);
+
+        assert!(ht16k33
+            .update_buffer_with_formatted_float(Index::One, 20., &format)
+            .is_ok());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0000_0000);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b0000_0000);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b0101_1011);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0011_1111);
+
+        i2c = ht16k33.destroy();
+        i2c.done();
+    }
+
+    #[test]
+    fn update_buffer_with_fixed() {
+        let expectations = [];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut ht16k33 = HT16K33::new(i2c, ADDRESS);
+
+        assert!(ht16k33
+            .update_buffer_with_fixed(Index::One, 2345, 2)
+            .is_ok());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0101_1011);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b1100_1111);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b0110_0110);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0110_1101);
+
+        // The fractional digits are reduced to fit the sign, 23.45 rounds to 23.5
+        assert!(ht16k33
+            .update_buffer_with_fixed(Index::One, -2345, 2)
+            .is_ok());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0100_0000);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b0101_1011);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b1100_1111);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0110_1101);
+
+        assert!(ht16k33.update_buffer_with_fixed(Index::Two, 5, 3).is_ok());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0100_0000);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b1011_1111);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b0011_1111);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0000_0110);
+
+        let format = FloatFormat::new(1, 10).rounding(Rounding::HalfEven);
+        assert!(ht16k33
+            .update_buffer_with_formatted_fixed(Index::One, 2345, 2, &format)
+            .is_ok());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0000_0000);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b0101_1011);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b1100_1111);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0110_0110);
+
+        assert!(ht16k33
+            .update_buffer_with_fixed(Index::One, 12345, 0)
+            .is_err());
+
+        i2c = ht16k33.destroy();
+        i2c.done();
+    }
+
+    #[test]
+    fn update_buffer_with_measurement() {
+        let expectations = [];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut ht16k33 = HT16K33::new(i2c, ADDRESS);
+
+        ht16k33.update_buffer_with_segments(Index::Four, 0b1000_0001);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b1000_0001);
+
+        assert!(ht16k33
+            .update_buffer_with_measurement(Index::One, 21.4, Unit::Fahrenheit)
+            .is_ok());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0101_1011);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b0000_0110);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b0110_0011);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0111_0001);
+
+        assert!(ht16k33
+            .update_buffer_with_measurement(Index::One, 1.25, Unit::Ampere)
+            .is_ok());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b1000_0110);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b0101_1011);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b0110_1101);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0111_0111);
+
+        i2c = ht16k33.destroy();
+        i2c.done();
+    }
+
+    #[test]
+    fn update_buffer_with_hex_and_binary() {
+        let expectations = [];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut ht16k33 = HT16K33::new(i2c, ADDRESS);
+
+        ht16k33.update_buffer_with_hex(0x0A5F);
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0011_1111);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b0111_0111);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b0110_1101);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0111_0001);
+
+        ht16k33.update_buffer_with_hex_u8(0x3C);
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0000_0000);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b0100_1111);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b0011_1001);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0111_0100);
+
+        ht16k33.update_buffer_with_binary(0b1000_0100_0011_1111);
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0010_0000);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b0000_0010);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b0001_0100);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0011_0110);
+
+        i2c = ht16k33.destroy();
+        i2c.done();
+    }
+
+    #[test]
+    fn overflow_clamp_blink() {
+        let expectations = [hal::i2c::Transaction::write(ADDRESS, std::vec![0x83])];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut ht16k33 = HT16K33::new(i2c, ADDRESS);
+        ht16k33.set_display(Display::TWO_HZ).unwrap();
+
+        // The driver can not blink on request, nothing is drawn or sent.
+        let format = FloatFormat::new(0, 10).overflow(Overflow::ClampBlink);
+        assert!(matches!(
+            ht16k33.update_buffer_with_formatted_fixed(Index::One, 12345, 0, &format),
+            Err(Error::BlinkUnsupported)
+        ));
+        assert!(matches!(
+            ht16k33.update_buffer_with_formatted_fixed(Index::One, 1234, 0, &format),
+            Err(Error::BlinkUnsupported)
+        ));
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0);
+        assert_eq!(*ht16k33.display(), Display::TWO_HZ);
+
+        i2c = ht16k33.destroy();
+        i2c.done();
+    }
 }
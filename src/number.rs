@@ -0,0 +1,1028 @@
+// Copyright (c) 2020 Karl Thorén <karl.h.thoren@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Number formatting shared by the numeric update methods.
+//!
+//! Values are rounded with integer arithmetic on the exact binary value of the float,
+//! so no precision is lost by scaling in floating point before rounding. Fixed-point
+//! values never touch floating point at all.
+
+use core::cmp::Ordering;
+
+use crate::{char_bits, AsciiChar, Error, Index, SevenSegment};
+
+/// Rounding applied to the last displayed digit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Rounding {
+    /// Round ties away from zero, 0.125 with 2 fractional digits becomes 0.13.
+    #[default]
+    HalfAwayFromZero,
+    /// Round ties to the nearest even digit, 0.125 with 2 fractional digits becomes 0.12.
+    HalfEven,
+}
+
+/// Notation used for values that do not fit as plain digits.
+///
+/// Exponents and suffixes are only used in base 10, the exponent can be at most two digits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Notation {
+    /// Plain digits only, an error is returned if the value does not fit.
+    #[default]
+    Plain,
+    /// Fall back to exponent form, like 1.2E5 or 4E-3, for values too large to fit
+    /// or too small to show any significant digit.
+    Scientific,
+    /// Fall back to a suffix for values too large to fit, `k`, `M`, `G` or `t`,
+    /// like 12.3k or 4.5M. Otherwise the same as `Scientific`.
+    Engineering,
+}
+
+/// What to show when a value does not fit on the display.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Overflow {
+    /// Return `Error::InsufficientDigits` and leave the buffer untouched.
+    #[default]
+    Error,
+    /// Show the largest value that fits, like 9999 or -999.
+    Clamp,
+    /// Show a dash on every digit.
+    Dashes,
+    /// Show `OFL`, with an upper case O.
+    Ofl,
+    /// Show `Err`.
+    Err,
+    /// Like `Clamp`, and blink the display until a value fits again.
+    ///
+    /// The blinking is recorded with [`SevenSegment::set_blink`], a [`Frame`](crate::Frame)
+    /// sends it with the next flush. The `HT16K33` update methods never talk to the device,
+    /// so there `Error::BlinkUnsupported` is returned and the buffer is left untouched,
+    /// draw on a `Frame` to clamp and blink.
+    ClampBlink,
+}
+
+/// Unit shown after a measurement, taking up one or two digits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    /// `°`, a raised small o.
+    Degree,
+    /// `°C`.
+    Celsius,
+    /// `°F`.
+    Fahrenheit,
+    /// `%`, a raised and a lowered small o.
+    Percent,
+    /// `V`, shown as `U`.
+    Volt,
+    /// `A`.
+    Ampere,
+    /// `H`, like relative humidity.
+    Humidity,
+    /// `P`, like pressure or power.
+    Pressure,
+}
+
+/// Segments of a raised small o.
+pub(crate) const DEGREE_SIGN: u8 = 0x63;
+
+impl Unit {
+    /// Segments of the unit, one entry per digit.
+    fn segments(self) -> &'static [u8] {
+        match self {
+            Unit::Degree => &[DEGREE_SIGN],
+            Unit::Celsius => &[DEGREE_SIGN, 0x39],
+            Unit::Fahrenheit => &[DEGREE_SIGN, 0x71],
+            Unit::Percent => &[DEGREE_SIGN, 0x5C],
+            Unit::Volt => &[0x3E],
+            Unit::Ampere => &[0x77],
+            Unit::Humidity => &[0x76],
+            Unit::Pressure => &[0x73],
+        }
+    }
+}
+
+/// Options for formatting a float on the display.
+///
+/// # Examples
+///
+/// ```
+/// use adafruit_7segment::{FloatFormat, Notation, Overflow, Rounding, Unit};
+///
+/// // Two fractional digits in base 10, rounding ties to even.
+/// let format = FloatFormat::new(2, 10).rounding(Rounding::HalfEven);
+///
+/// // As many fractional digits as fit, without trailing zeros.
+/// let format = FloatFormat::auto(10).trim_trailing_zeros(true);
+///
+/// // 12345 is shown as 12.3k.
+/// let format = FloatFormat::auto(10).notation(Notation::Engineering);
+///
+/// // 12345 is shown as 9999.
+/// let format = FloatFormat::new(0, 10).overflow(Overflow::Clamp);
+///
+/// // 23.46 is shown as 23.5°C.
+/// let format = FloatFormat::auto(10).unit(Unit::Celsius);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FloatFormat {
+    // `None` shows as many fractional digits as fit.
+    fractional_digits: Option<u8>,
+    base: u8,
+    rounding: Rounding,
+    trim_trailing_zeros: bool,
+    notation: Notation,
+    // `None` uses the display-wide policy.
+    overflow: Option<Overflow>,
+    unit: Option<Unit>,
+}
+
+impl FloatFormat {
+    /// Create a format with the wanted number of fractional digits and base.
+    /// The number of fractional digits is reduced if the value does not fit.
+    /// Formatting with a base outside 2 to 36 returns `Error::NotValidChar`.
+    pub fn new(fractional_digits: u8, base: u8) -> Self {
+        FloatFormat {
+            fractional_digits: Some(fractional_digits),
+            base,
+            rounding: Rounding::default(),
+            trim_trailing_zeros: false,
+            notation: Notation::default(),
+            overflow: None,
+            unit: None,
+        }
+    }
+
+    /// Create a format showing as many significant digits as fit, in the given base.
+    /// With four free digits this gives 1.234, 12.34, 123.4 and 1234.
+    pub fn auto(base: u8) -> Self {
+        FloatFormat {
+            fractional_digits: None,
+            ..FloatFormat::new(0, base)
+        }
+    }
+
+    /// Set the rounding used for the last displayed digit.
+    pub fn rounding(mut self, rounding: Rounding) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// Remove zeros at the end of the fractional part, and the dot if no fractional digits remain.
+    pub fn trim_trailing_zeros(mut self, trim: bool) -> Self {
+        self.trim_trailing_zeros = trim;
+        self
+    }
+
+    /// Set the notation used for values that do not fit as plain digits.
+    pub fn notation(mut self, notation: Notation) -> Self {
+        self.notation = notation;
+        self
+    }
+
+    /// Set what to show when the value does not fit, overriding the display-wide policy.
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = Some(overflow);
+        self
+    }
+
+    /// Show a unit after the value, the digits it takes up are not available to the value.
+    pub fn unit(mut self, unit: Unit) -> Self {
+        self.unit = Some(unit);
+        self
+    }
+}
+
+/// A number decoded into parts that can be formatted without floating point arithmetic.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Number {
+    /// Not a number.
+    NaN,
+    /// Positive or negative infinity.
+    Infinite { negative: bool },
+    /// The value `mantissa * 2^exponent`.
+    Finite {
+        negative: bool,
+        mantissa: u64,
+        exponent: i32,
+    },
+    /// The value `value / 10^scale`.
+    Fixed {
+        negative: bool,
+        value: u32,
+        scale: u8,
+    },
+}
+
+impl From<f32> for Number {
+    fn from(value: f32) -> Number {
+        let bits = value.to_bits();
+        let negative = (bits >> 31) != 0;
+        let biased_exponent = ((bits >> 23) & 0xFF) as i32;
+        let fraction = u64::from(bits & 0x007F_FFFF);
+        match biased_exponent {
+            0xFF if fraction != 0 => Number::NaN,
+            0xFF => Number::Infinite { negative },
+            // Subnormal
+            0 => Number::finite(negative, fraction, -149),
+            _ => Number::finite(negative, fraction | 0x0080_0000, biased_exponent - 150),
+        }
+    }
+}
+
+impl From<f64> for Number {
+    fn from(value: f64) -> Number {
+        let bits = value.to_bits();
+        let negative = (bits >> 63) != 0;
+        let biased_exponent = ((bits >> 52) & 0x7FF) as i32;
+        let fraction = bits & 0x000F_FFFF_FFFF_FFFF;
+        match biased_exponent {
+            0x7FF if fraction != 0 => Number::NaN,
+            0x7FF => Number::Infinite { negative },
+            // Subnormal
+            0 => Number::finite(negative, fraction, -1074),
+            _ => Number::finite(
+                negative,
+                fraction | 0x0010_0000_0000_0000,
+                biased_exponent - 1075,
+            ),
+        }
+    }
+}
+
+impl Number {
+    fn finite(negative: bool, mantissa: u64, exponent: i32) -> Number {
+        Number::Finite {
+            // Negative zero is shown without sign.
+            negative: negative && mantissa != 0,
+            mantissa,
+            exponent,
+        }
+    }
+
+    /// A fixed-point value, `value / 10^scale_digits`.
+    pub(crate) fn fixed(value: i32, scale_digits: u8) -> Number {
+        Number::Fixed {
+            negative: value < 0,
+            value: value.unsigned_abs(),
+            scale: scale_digits,
+        }
+    }
+
+    /// The magnitude multiplied by `base^digits`, rounded to an integer.
+    /// A negative `digits` divides by the base instead.
+    /// Returns `None` if the result does not fit in an `u128`.
+    pub(crate) fn scaled(&self, base: u32, digits: i32, rounding: Rounding) -> Option<u128> {
+        let (mut numerator, exponent, scale) = match *self {
+            Number::Finite {
+                mantissa, exponent, ..
+            } => (u128::from(mantissa), exponent, 0),
+            Number::Fixed { value, scale, .. } => (u128::from(value), 0, scale),
+            _ => return None,
+        };
+        // `None` when the denominator does not fit, the value is then far below one half.
+        let mut denominator = 10u128.checked_pow(u32::from(scale));
+        let factor = u128::from(base).checked_pow(digits.unsigned_abs());
+        if digits >= 0 {
+            numerator = numerator.checked_mul(factor?)?;
+        } else {
+            denominator = denominator.zip(factor).and_then(|(d, f)| d.checked_mul(f));
+        }
+        if exponent >= 0 {
+            if numerator.leading_zeros() <= exponent as u32 {
+                return None;
+            }
+            numerator <<= exponent;
+        } else {
+            let shift = exponent.unsigned_abs();
+            denominator = denominator.filter(|d| d.leading_zeros() > shift);
+            denominator = denominator.map(|d| d << shift);
+        }
+        let denominator = match denominator {
+            Some(denominator) => denominator,
+            None => return Some(0),
+        };
+        let quotient = numerator / denominator;
+        let remainder = numerator % denominator;
+        Some(round(
+            quotient,
+            remainder.cmp(&(denominator - remainder)),
+            rounding,
+        ))
+    }
+
+    /// Estimate of `floor(log10(|value|))` for a non-zero value, off by at most one.
+    fn decimal_exponent(&self) -> i32 {
+        match *self {
+            Number::Finite {
+                mantissa, exponent, ..
+            } => {
+                // floor(log2(value)) * log10(2), with log10(2) close to 1233 / 4096.
+                let log2 = exponent + 63 - mantissa.leading_zeros() as i32;
+                (log2 * 1233) >> 12
+            }
+            Number::Fixed { value, scale, .. } => {
+                let mut digits = 0;
+                let mut value = value;
+                while value >= 10 {
+                    value /= 10;
+                    digits += 1;
+                }
+                digits - i32::from(scale)
+            }
+            _ => 0,
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        match *self {
+            Number::Finite { mantissa, .. } => mantissa == 0,
+            Number::Fixed { value, .. } => value == 0,
+            _ => false,
+        }
+    }
+}
+
+/// Round the quotient given how the remainder compares to one half.
+fn round(quotient: u128, remainder: Ordering, rounding: Rounding) -> u128 {
+    match (remainder, rounding) {
+        (Ordering::Greater, _) | (Ordering::Equal, Rounding::HalfAwayFromZero) => quotient + 1,
+        (Ordering::Equal, Rounding::HalfEven) => quotient + (quotient & 1),
+        (Ordering::Less, _) => quotient,
+    }
+}
+
+/// Write text right aligned into the digits `start..end`, blanking the digits before it.
+pub(crate) fn update_buffer_with_text<S, E>(
+    display: &mut S,
+    start: u8,
+    end: u8,
+    text: &[u8],
+) -> Result<(), Error>
+where
+    S: SevenSegment<E> + ?Sized,
+{
+    if text.len() > usize::from(end - start) {
+        return Err(Error::InsufficientDigits);
+    }
+    let first = end - text.len() as u8;
+    for pos in start..end {
+        let value = if pos < first {
+            AsciiChar::Space
+        } else {
+            AsciiChar::from_ascii(text[usize::from(pos - first)])
+                .map_err(|_| Error::NotValidChar)?
+        };
+        display.update_buffer_with_char(Index::from(pos), value)?;
+    }
+    Ok(())
+}
+
+/// Segments of `OFL`. The upper case O is drawn like a zero, `char_bits` gives a lower case o.
+const OFL: [u8; 3] = [0x3F, 0x71, 0x38];
+
+/// Write segments right aligned in `start..end`, blanking the digits before them.
+fn update_buffer_with_glyphs<S, E>(display: &mut S, start: u8, end: u8, glyphs: &[u8])
+where
+    S: SevenSegment<E> + ?Sized,
+{
+    let first = end - glyphs.len() as u8;
+    for pos in start..end {
+        let segments = if pos < first {
+            0
+        } else {
+            glyphs[usize::from(pos - first)]
+        };
+        display.update_buffer_with_segments(Index::from(pos), segments);
+    }
+}
+
+/// Write a dash on every digit in `start..end`.
+fn update_buffer_with_dashes<S, E>(display: &mut S, start: u8, end: u8) -> Result<(), Error>
+where
+    S: SevenSegment<E> + ?Sized,
+{
+    for pos in start..end {
+        display.update_buffer_with_char(Index::from(pos), AsciiChar::Minus)?;
+    }
+    Ok(())
+}
+
+/// Segments shown after the digits, an exponent or a unit.
+#[derive(Clone, Copy, Default)]
+struct Suffix {
+    segments: [u8; 4],
+    len: u8,
+}
+
+impl Suffix {
+    fn push(&mut self, segments: u8) {
+        self.segments[usize::from(self.len)] = segments;
+        self.len += 1;
+    }
+
+    /// Add an ascii character, it must be one the display can show.
+    fn push_char(&mut self, c: u8) {
+        let segments = AsciiChar::from_ascii(c)
+            .ok()
+            .and_then(|value| char_bits(value).ok());
+        debug_assert!(segments.is_some());
+        self.push(segments.unwrap_or(0));
+    }
+
+    fn as_segments(&self) -> &[u8] {
+        &self.segments[..usize::from(self.len)]
+    }
+
+    /// Exponent form, like `E5` or `E-3`.
+    fn exponent(exponent: i32) -> Suffix {
+        let mut suffix = Suffix::default();
+        suffix.push_char(b'E');
+        if exponent < 0 {
+            suffix.push_char(b'-');
+        }
+        let exponent = exponent.unsigned_abs();
+        if exponent >= 10 {
+            suffix.push_char(b'0' + (exponent / 10 % 10) as u8);
+        }
+        suffix.push_char(b'0' + (exponent % 10) as u8);
+        suffix
+    }
+}
+
+/// Digits to show, `value` with `fractional_digits` digits after the dot followed by `suffix`.
+struct Rendering {
+    value: u128,
+    fractional_digits: u8,
+    suffix: Suffix,
+}
+
+/// The number divided by `base^shift` rounded to fit in `numeric_digits` digits,
+/// reducing the fractional digits as needed.
+fn fit(
+    number: &Number,
+    format: &FloatFormat,
+    numeric_digits: u8,
+    shift: i32,
+) -> Option<(u128, u8)> {
+    if numeric_digits == 0 {
+        return None;
+    }
+    let base = u32::from(format.base);
+
+    // Calculate the upper bound given the number of digits available
+    let too_big = u128::from(base).pow(u32::from(numeric_digits));
+
+    // A leading zero is always shown before the fractional digits
+    let mut fractional_digits = format
+        .fractional_digits
+        .unwrap_or(u8::MAX)
+        .min(numeric_digits - 1);
+
+    // If the number is too large, reduce fractional digits
+    loop {
+        let digits = i32::from(fractional_digits) - shift;
+        match number.scaled(base, digits, format.rounding) {
+            Some(value) if value < too_big => return Some((value, fractional_digits)),
+            _ if fractional_digits == 0 => return None,
+            _ => fractional_digits -= 1,
+        }
+    }
+}
+
+/// Exponent form with as many significant digits as fit, like 1.2E5 or 4E-3.
+fn scientific(number: &Number, format: &FloatFormat, width: u8) -> Option<Rendering> {
+    let estimate = number.decimal_exponent();
+    for exponent in estimate - 1..=estimate + 2 {
+        if exponent.abs() >= 100 {
+            return None;
+        }
+        let suffix = Suffix::exponent(exponent);
+        let numeric_digits = match width.checked_sub(suffix.len) {
+            Some(digits) if digits > 0 => digits,
+            _ => continue,
+        };
+        // Only a single digit is shown before the dot
+        let fractional_digits = format
+            .fractional_digits
+            .unwrap_or(u8::MAX)
+            .min(numeric_digits - 1);
+        let digits = i32::from(fractional_digits) - exponent;
+        let one = 10u128.pow(u32::from(fractional_digits));
+        let value = match number.scaled(10, digits, format.rounding) {
+            Some(value) if value >= one && value < one * 10 => value,
+            _ => continue,
+        };
+        return Some(Rendering {
+            value,
+            fractional_digits,
+            suffix,
+        });
+    }
+    None
+}
+
+/// Suffixes for thousands, millions, billions and trillions.
+const ENGINEERING_SUFFIXES: [u8; 4] = [b'k', b'M', b'G', b't'];
+
+/// Plain digits divided by a power of 1000 followed by a suffix, like 12.3k or 4.5M.
+fn engineering(number: &Number, format: &FloatFormat, width: u8) -> Option<Rendering> {
+    let numeric_digits = width.checked_sub(1)?;
+    for (i, &c) in ENGINEERING_SUFFIXES.iter().enumerate() {
+        if let Some((value, fractional_digits)) =
+            fit(number, format, numeric_digits, 3 * (i as i32 + 1))
+        {
+            let mut suffix = Suffix::default();
+            suffix.push_char(c);
+            return Some(Rendering {
+                value,
+                fractional_digits,
+                suffix,
+            });
+        }
+    }
+    None
+}
+
+/// Work out the digits to show within `width` digits, the sign excluded.
+fn layout(number: &Number, format: &FloatFormat, width: u8) -> Result<Rendering, Error> {
+    let plain = fit(number, format, width, 0).map(|(value, fractional_digits)| Rendering {
+        value,
+        fractional_digits,
+        suffix: Suffix::default(),
+    });
+    // Exponent notation is only readable in base 10.
+    if format.notation == Notation::Plain || format.base != 10 {
+        return plain.ok_or(Error::InsufficientDigits);
+    }
+    match plain {
+        // Only fall back if all significant digits would be lost.
+        Some(plain) if plain.value != 0 || number.is_zero() => Ok(plain),
+        Some(_) => scientific(number, format, width).ok_or(Error::InsufficientDigits),
+        None => {
+            let fallback = if format.notation == Notation::Engineering {
+                engineering(number, format, width)
+            } else {
+                None
+            };
+            fallback
+                .or_else(|| scientific(number, format, width))
+                .ok_or(Error::InsufficientDigits)
+        }
+    }
+}
+
+/// Write a formatted number right aligned into the digits `start..end`,
+/// blanking the digits before it. Values that do not fit are handled by the overflow
+/// policy of the format, or else of the display. The buffer is left untouched on error.
+pub(crate) fn update_buffer_with_number<S, E>(
+    display: &mut S,
+    start: u8,
+    end: u8,
+    number: Number,
+    format: &FloatFormat,
+) -> Result<(), Error>
+where
+    S: SevenSegment<E> + ?Sized,
+{
+    let (negative, special): (bool, &[u8]) = match number {
+        Number::NaN => (false, b"nAn"),
+        Number::Infinite { negative: false } => (false, b"inF"),
+        Number::Infinite { negative: true } => (true, b"-inF"),
+        Number::Finite { negative, .. } | Number::Fixed { negative, .. } => (negative, b""),
+    };
+
+    let base = u32::from(format.base);
+    if !(2..=36).contains(&base) {
+        return Err(Error::NotValidChar);
+    }
+
+    let unit = format.unit.map_or(&[][..], Unit::segments);
+
+    // Available digits on display, the sign and the unit take up digits too
+    let width = (end - start)
+        .saturating_sub(negative as u8)
+        .saturating_sub(unit.len() as u8);
+    let overflow = format.overflow.unwrap_or_else(|| display.overflow());
+    // NaN and infinity are shown as text, going through the same overflow policy.
+    let laid_out = match number {
+        Number::Finite { .. } | Number::Fixed { .. } => layout(&number, format, width).map(Some),
+        _ if special.len() <= usize::from(end - start) => Ok(None),
+        _ => Err(Error::InsufficientDigits),
+    };
+    if overflow == Overflow::ClampBlink {
+        display.set_blink(laid_out.is_err())?;
+    }
+    let mut rendering = match laid_out {
+        Ok(Some(rendering)) => rendering,
+        Ok(None) => return update_buffer_with_text(display, start, end, special),
+        Err(error) => match overflow {
+            Overflow::Error => return Err(error),
+            Overflow::Clamp | Overflow::ClampBlink if width > 0 && number != Number::NaN => {
+                // All digits at their highest value
+                Rendering {
+                    value: u128::from(base).pow(u32::from(width)) - 1,
+                    fractional_digits: 0,
+                    suffix: Suffix::default(),
+                }
+            }
+            Overflow::Ofl if end - start >= 3 => {
+                update_buffer_with_glyphs(display, start, end, &OFL);
+                return Ok(());
+            }
+            Overflow::Err if end - start >= 3 => {
+                return update_buffer_with_text(display, start, end, b"Err")
+            }
+            _ => return update_buffer_with_dashes(display, start, end),
+        },
+    };
+    for &segments in unit {
+        rendering.suffix.push(segments);
+    }
+    render(display, start, end, negative, rendering, format)
+}
+
+/// Write the digits of a rendering right aligned into the digits `start..end`,
+/// blanking the digits before it.
+fn render<S, E>(
+    display: &mut S,
+    start: u8,
+    end: u8,
+    negative: bool,
+    rendering: Rendering,
+    format: &FloatFormat,
+) -> Result<(), Error>
+where
+    S: SevenSegment<E> + ?Sized,
+{
+    let base = u32::from(format.base);
+    let Rendering {
+        value: mut display_number,
+        mut fractional_digits,
+        suffix,
+    } = rendering;
+
+    if format.trim_trailing_zeros {
+        while fractional_digits != 0 && display_number % u128::from(base) == 0 {
+            display_number /= u128::from(base);
+            fractional_digits -= 1;
+        }
+    }
+
+    // Digit we're working on, counting down from the end
+    let mut pos = end;
+    for &segments in suffix.as_segments().iter().rev() {
+        pos -= 1;
+        display.update_buffer_with_segments(Index::from(pos), segments);
+    }
+
+    let mut i = 0;
+    while display_number != 0 || i <= fractional_digits {
+        pos -= 1;
+        let digit_index = Index::from(pos);
+        // Write out the current digit
+        display.update_buffer_with_digit(digit_index, (display_number % u128::from(base)) as u8);
+        // Add the decimal if necessary
+        if fractional_digits != 0 && i == fractional_digits {
+            display.update_buffer_with_dot(digit_index, true);
+        }
+        // Move the number along
+        display_number /= u128::from(base);
+        i += 1;
+    }
+
+    if negative {
+        // Add the minus sign
+        pos -= 1;
+        display.update_buffer_with_char(Index::from(pos), AsciiChar::Minus)?;
+    }
+
+    // Clear any remaining segments
+    for pos in start..pos {
+        display.update_buffer_with_char(Index::from(pos), AsciiChar::Space)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use self::std::format;
+    use self::std::string::{String, ToString};
+    use super::*;
+    use crate::DecodedBuffer;
+    use embedded_hal_mock::i2c::Mock as I2cMock;
+    use ht16k33::HT16K33;
+    use proptest::prelude::*;
+
+    const ADDRESS: u8 = 0;
+
+    /// Read back the digits of the display as text.
+    fn text(ht16k33: &HT16K33<I2cMock>) -> String {
+        DecodedBuffer::new(ht16k33.display_buffer()).to_string()
+    }
+
+    /// Format a value on a whole display and read it back.
+    fn formatted(value: f64, format: &FloatFormat) -> Result<String, Error> {
+        let mut ht16k33 = HT16K33::new(I2cMock::new(&[]), ADDRESS);
+        update_buffer_with_number(&mut ht16k33, 0, 4, value.into(), format)?;
+        Ok(text(&ht16k33))
+    }
+
+    /// Reference rounding from the exact decimal expansion printed by `std`.
+    fn reference(value: f64, digits: u8, rounding: Rounding) -> u128 {
+        reference_from_exact(&format!("{:.1100}", value.abs()), digits, rounding)
+    }
+
+    /// Reference rounding of `value / 10^shift`.
+    fn reference_shifted(value: f64, shift: usize, rounding: Rounding) -> u128 {
+        let exact = format!("{:.1100}", value.abs());
+        let point = exact.find('.').unwrap();
+        let digits = format!("{:0>1$}", &exact[..point], shift + 1);
+        let (integer, moved) = digits.split_at(digits.len() - shift);
+        let exact = format!("{}.{}{}", integer, moved, &exact[point + 1..]);
+        reference_from_exact(&exact, 0, rounding)
+    }
+
+    /// Reference rounding of an exact decimal string with a fractional part.
+    fn reference_from_exact(exact: &str, digits: u8, rounding: Rounding) -> u128 {
+        let (integer, fraction) = exact.split_at(exact.find('.').unwrap());
+        let fraction = &fraction[1..];
+        let kept: String = integer
+            .chars()
+            .chain(fraction[..usize::from(digits)].chars())
+            .collect();
+        let quotient: u128 = kept.parse().unwrap();
+        let rest = fraction[usize::from(digits)..].trim_end_matches('0');
+        let remainder = match rest.chars().next() {
+            None => Ordering::Less,
+            Some(first) if first < '5' => Ordering::Less,
+            Some('5') if rest.len() == 1 => Ordering::Equal,
+            Some(_) => Ordering::Greater,
+        };
+        round(quotient, remainder, rounding)
+    }
+
+    #[test]
+    fn scaled_ties() {
+        let number = Number::from(0.125f64);
+        assert_eq!(number.scaled(10, 2, Rounding::HalfAwayFromZero), Some(13));
+        assert_eq!(number.scaled(10, 2, Rounding::HalfEven), Some(12));
+        let number = Number::from(2.675f64);
+        assert_eq!(number.scaled(10, 2, Rounding::HalfAwayFromZero), Some(267));
+        assert_eq!(
+            Number::from(1e300f64).scaled(10, 2, Rounding::HalfEven),
+            None
+        );
+        assert_eq!(
+            Number::from(1e-300f64).scaled(10, 3, Rounding::HalfEven),
+            Some(0)
+        );
+        assert_eq!(
+            Number::fixed(-2345, 2).scaled(10, 1, Rounding::HalfAwayFromZero),
+            Some(235)
+        );
+        assert_eq!(
+            Number::fixed(-2345, 2).scaled(10, 1, Rounding::HalfEven),
+            Some(234)
+        );
+        assert_eq!(
+            Number::fixed(i32::MAX, 200).scaled(10, 3, Rounding::HalfEven),
+            Some(0)
+        );
+        assert_eq!(
+            Number::from(1e38f64).scaled(10, -37, Rounding::HalfEven),
+            Some(10)
+        );
+        assert_eq!(
+            Number::from(1e38f64).scaled(10, -40, Rounding::HalfEven),
+            Some(0)
+        );
+        assert_eq!(Number::from(f32::NAN), Number::NaN);
+        assert_eq!(
+            Number::from(f64::NEG_INFINITY),
+            Number::Infinite { negative: true }
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn scaled_f64_matches_std(value in -1e5f64..1e5f64, digits in 0u8..4) {
+            let number = Number::from(value);
+            prop_assert_eq!(
+                number.scaled(10, digits.into(), Rounding::HalfEven),
+                format!("{:.*}", usize::from(digits), value.abs()).replace('.', "").parse().ok()
+            );
+            prop_assert_eq!(
+                number.scaled(10, digits.into(), Rounding::HalfAwayFromZero),
+                Some(reference(value, digits, Rounding::HalfAwayFromZero))
+            );
+        }
+
+        #[test]
+        fn scaled_f32_matches_std(value in -1e5f32..1e5f32, digits in 0u8..4) {
+            let number = Number::from(value);
+            for &rounding in &[Rounding::HalfEven, Rounding::HalfAwayFromZero] {
+                prop_assert_eq!(
+                    number.scaled(10, digits.into(), rounding),
+                    Some(reference(f64::from(value), digits, rounding))
+                );
+            }
+        }
+
+        #[test]
+        fn scaled_ties_match_std(halves in 0u32..100_000, digits in 0u8..3) {
+            // (2n + 1) / 2^(digits + 1) ends in exactly 5 at the first dropped digit.
+            let value = f64::from(halves * 2 + 1) / f64::from(2u32.pow(u32::from(digits) + 1));
+            let number = Number::from(value);
+            prop_assert_eq!(
+                number.scaled(10, digits.into(), Rounding::HalfEven),
+                format!("{:.*}", usize::from(digits), value).replace('.', "").parse().ok()
+            );
+            prop_assert_eq!(
+                number.scaled(10, digits.into(), Rounding::HalfAwayFromZero),
+                Some(reference(value, digits, Rounding::HalfAwayFromZero))
+            );
+        }
+
+        #[test]
+        fn scaled_down_matches_reference(value in -1e9f64..1e9f64, shift in 1usize..6) {
+            let number = Number::from(value);
+            for &rounding in &[Rounding::HalfEven, Rounding::HalfAwayFromZero] {
+                prop_assert_eq!(
+                    number.scaled(10, -(shift as i32), rounding),
+                    Some(reference_shifted(value, shift, rounding))
+                );
+            }
+        }
+
+        #[test]
+        fn scaled_fixed_matches_reference(value: i32, scale in 0u8..12, digits in 0u8..4) {
+            let number = Number::fixed(value, scale);
+            // Place the decimal point in the digits of the integer.
+            let padded = format!("{:0>1$}", value.unsigned_abs(), usize::from(scale) + 1);
+            let (integer, fraction) = padded.split_at(padded.len() - usize::from(scale));
+            let exact = format!("{}.{:0<4}", integer, fraction);
+            for &rounding in &[Rounding::HalfEven, Rounding::HalfAwayFromZero] {
+                prop_assert_eq!(
+                    number.scaled(10, digits.into(), rounding),
+                    Some(reference_from_exact(&exact, digits, rounding))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn notation() {
+        let format = FloatFormat::auto(10).notation(Notation::Scientific);
+        assert_eq!(formatted(123456., &format).unwrap(), "1.2E5");
+        assert_eq!(formatted(-123456., &format).unwrap(), "-1E5");
+        assert_eq!(formatted(99999., &format).unwrap(), "1.0E5");
+        assert_eq!(formatted(0.004, &format).unwrap(), "0.004");
+        assert_eq!(formatted(0.0004, &format).unwrap(), "4E-4");
+        let two_digits = FloatFormat::new(2, 10).notation(Notation::Scientific);
+        assert_eq!(formatted(0.004, &two_digits).unwrap(), "4E-3");
+        assert_eq!(formatted(0.005, &two_digits).unwrap(), " 0.01");
+        assert_eq!(formatted(1.5e12, &format).unwrap(), "2E12");
+        assert_eq!(formatted(0., &format).unwrap(), "0.000");
+        assert!(formatted(1e100, &format).is_err());
+        assert!(formatted(1e-10, &format).is_err());
+        assert_eq!(formatted(1e-10, &FloatFormat::auto(10)).unwrap(), "0.000");
+
+        let format = FloatFormat::auto(10).notation(Notation::Engineering);
+        assert_eq!(formatted(12345., &format).unwrap(), "12.3k");
+        assert_eq!(formatted(4.5e6, &format).unwrap(), "4.50M");
+        assert_eq!(formatted(-4.5e6, &format).unwrap(), "-4.5M");
+        assert_eq!(formatted(999_999., &format).unwrap(), "1.00M");
+        assert_eq!(formatted(7.25e9, &format).unwrap(), "7.25G");
+        assert_eq!(formatted(1e15, &format).unwrap(), "1E15");
+        assert_eq!(formatted(0.00042, &format).unwrap(), "4E-4");
+
+        // Exponents are not used in other bases.
+        let format = FloatFormat::auto(16).notation(Notation::Scientific);
+        assert!(formatted(1e6, &format).is_err());
+
+        // Mega and milli have glyphs of their own.
+        assert_ne!(
+            char_bits(AsciiChar::M).unwrap(),
+            char_bits(AsciiChar::m).unwrap()
+        );
+        assert!(matches!(
+            formatted(1., &FloatFormat::new(0, 37)),
+            Err(Error::NotValidChar)
+        ));
+    }
+
+    #[test]
+    fn overflow() {
+        let format = FloatFormat::new(1, 10);
+        assert!(matches!(
+            formatted(12345., &format.overflow(Overflow::Error)),
+            Err(Error::InsufficientDigits)
+        ));
+        assert_eq!(
+            formatted(12345., &format.overflow(Overflow::Clamp)).unwrap(),
+            "9999"
+        );
+        assert_eq!(
+            formatted(-12345., &format.overflow(Overflow::Clamp)).unwrap(),
+            "-999"
+        );
+        assert_eq!(
+            formatted(12345., &format.overflow(Overflow::Dashes)).unwrap(),
+            "----"
+        );
+        // The upper case O looks like a zero, which is what it decodes to.
+        assert_eq!(
+            formatted(12345., &format.overflow(Overflow::Ofl)).unwrap(),
+            " 0FL"
+        );
+        let mut ofl = HT16K33::new(I2cMock::new(&[]), ADDRESS);
+        ofl.update_buffer_with_segments(Index::Two, 0x3F);
+        ofl.update_buffer_with_char(Index::Three, AsciiChar::F)
+            .unwrap();
+        ofl.update_buffer_with_char(Index::Four, AsciiChar::L)
+            .unwrap();
+        let mut ht16k33 = HT16K33::new(I2cMock::new(&[]), ADDRESS);
+        let ofl_format = format.overflow(Overflow::Ofl);
+        update_buffer_with_number(&mut ht16k33, 0, 4, 12345.0.into(), &ofl_format).unwrap();
+        assert_eq!(ht16k33.display_buffer(), ofl.display_buffer());
+        assert_eq!(
+            formatted(12345., &format.overflow(Overflow::Err)).unwrap(),
+            " Err"
+        );
+        // Values that fit are not affected.
+        assert_eq!(
+            formatted(12.34, &format.overflow(Overflow::Dashes)).unwrap(),
+            " 12.3"
+        );
+
+        let format = FloatFormat::new(0, 16).overflow(Overflow::Clamp);
+        assert_eq!(formatted(70000., &format).unwrap(), "FFFF");
+
+        // NaN and infinity go through the policy when their text does not fit.
+        let mut ht16k33 = HT16K33::new(I2cMock::new(&[]), ADDRESS);
+        let inf = f64::NEG_INFINITY.into();
+        let format = FloatFormat::new(0, 10).overflow(Overflow::Error);
+        assert!(matches!(
+            update_buffer_with_number(&mut ht16k33, 1, 4, inf, &format),
+            Err(Error::InsufficientDigits)
+        ));
+        let format = FloatFormat::new(0, 10).overflow(Overflow::Clamp);
+        assert!(update_buffer_with_number(&mut ht16k33, 1, 4, inf, &format).is_ok());
+        assert_eq!(text(&ht16k33), " -99");
+        assert!(update_buffer_with_number(&mut ht16k33, 2, 4, f64::NAN.into(), &format).is_ok());
+        assert_eq!(text(&ht16k33), " ---");
+        let format = FloatFormat::new(0, 10).overflow(Overflow::Dashes);
+        assert!(update_buffer_with_number(&mut ht16k33, 1, 4, inf, &format).is_ok());
+        assert_eq!(text(&ht16k33), " ---");
+        let format = FloatFormat::new(0, 10).overflow(Overflow::Err);
+        assert!(update_buffer_with_number(&mut ht16k33, 1, 4, inf, &format).is_ok());
+        assert_eq!(text(&ht16k33), " Err");
+        assert_eq!(formatted(f64::NEG_INFINITY, &format).unwrap(), "-inF");
+
+        // Policies needing more room fall back to dashes.
+        let mut ht16k33 = HT16K33::new(I2cMock::new(&[]), ADDRESS);
+        let format = FloatFormat::new(0, 10).overflow(Overflow::Err);
+        assert!(update_buffer_with_number(&mut ht16k33, 2, 4, (-50.).into(), &format).is_ok());
+        assert_eq!(text(&ht16k33), "  --");
+        let format = FloatFormat::new(0, 10).overflow(Overflow::Clamp);
+        assert!(update_buffer_with_number(&mut ht16k33, 3, 4, (-5.).into(), &format).is_ok());
+        assert_eq!(text(&ht16k33), "  --");
+    }
+
+    #[test]
+    fn unit() {
+        let format = FloatFormat::auto(10).unit(Unit::Celsius);
+        assert_eq!(formatted(23.46, &format).unwrap(), "23°C");
+        assert_eq!(formatted(5.46, &format).unwrap(), "5.5°C");
+        assert_eq!(formatted(-5., &format).unwrap(), "-5°C");
+        assert!(formatted(123., &format).is_err());
+        assert_eq!(
+            formatted(123., &format.overflow(Overflow::Clamp)).unwrap(),
+            "99°C"
+        );
+        assert_eq!(
+            formatted(45.6, &FloatFormat::auto(10).unit(Unit::Percent)).unwrap(),
+            "46°o"
+        );
+        assert_eq!(
+            formatted(12.34, &FloatFormat::auto(10).unit(Unit::Volt)).unwrap(),
+            "12.3U"
+        );
+        assert_eq!(
+            formatted(-1.5, &FloatFormat::new(0, 10).unit(Unit::Degree)).unwrap(),
+            " -2°"
+        );
+
+        // A notation suffix comes before the unit.
+        let format = FloatFormat::auto(10)
+            .notation(Notation::Engineering)
+            .unit(Unit::Volt);
+        assert_eq!(formatted(12345., &format).unwrap(), "12kU");
+    }
+}
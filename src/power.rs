@@ -0,0 +1,258 @@
+// Copyright (c) 2020 Karl Thorén <karl.h.thoren@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Standby of the display to save power, and blanking it after inactivity.
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use ht16k33::{Oscillator, HT16K33};
+
+use crate::Error;
+
+/// Trait for putting the display in standby between updates.
+///
+/// In standby the system oscillator is off, the LEDs are dark and the HT16K33 draws
+/// only a few microamperes. The display RAM and settings are kept.
+pub trait PowerManagement<E> {
+    /// Put the display in standby by turning off the system oscillator.
+    fn sleep(&mut self) -> Result<(), Error>;
+
+    /// Wake the display from standby, sending the buffer and the display setup again.
+    fn wake(&mut self) -> Result<(), Error>;
+
+    /// Whether the display is in standby.
+    fn is_asleep(&self) -> bool;
+}
+
+impl<I2C, E> PowerManagement<E> for HT16K33<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Put the display in standby by turning off the system oscillator.
+    /// The buffer and the display setup are left as they are, the buffer can still
+    /// be updated while the display sleeps.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ht16k33::i2c_mock::I2cMock;
+    /// use ht16k33::HT16K33;
+    /// use adafruit_7segment::PowerManagement;
+    ///
+    /// // Create an I2C device.
+    /// let mut i2c = I2cMock::new();
+    ///
+    /// // The I2C device address.
+    /// const DISP_I2C_ADDR: u8 = 112;
+    ///
+    /// let mut ht16k33 = HT16K33::new(i2c, DISP_I2C_ADDR);
+    /// ht16k33.initialize().expect("Failed to initialize ht16k33");
+    ///
+    /// ht16k33.sleep().expect("Could not put the display in standby!");
+    /// assert!(ht16k33.is_asleep());
+    /// ```
+    fn sleep(&mut self) -> Result<(), Error> {
+        self.set_oscillator(Oscillator::OFF).map_err(|_| Error::Bus)
+    }
+
+    /// Wake the display from standby by turning on the system oscillator.
+    /// The buffer is sent again, with any updates made while asleep, and so is the
+    /// display setup, restoring the display if it lost power while asleep.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ht16k33::i2c_mock::I2cMock;
+    /// use ht16k33::{Display, HT16K33};
+    /// use adafruit_7segment::{Index, PowerManagement, SevenSegment};
+    ///
+    /// // Create an I2C device.
+    /// let mut i2c = I2cMock::new();
+    ///
+    /// // The I2C device address.
+    /// const DISP_I2C_ADDR: u8 = 112;
+    ///
+    /// let mut ht16k33 = HT16K33::new(i2c, DISP_I2C_ADDR);
+    /// ht16k33.initialize().expect("Failed to initialize ht16k33");
+    /// ht16k33.set_display(Display::ON).expect("Could not turn on the display!");
+    ///
+    /// ht16k33.sleep().expect("Could not put the display in standby!");
+    /// ht16k33.update_buffer_with_digit(Index::One, 1);
+    /// ht16k33.wake().expect("Could not wake the display!");
+    /// ```
+    fn wake(&mut self) -> Result<(), Error> {
+        self.set_oscillator(Oscillator::ON)
+            .map_err(|_| Error::Bus)?;
+        self.write_display_buffer().map_err(|_| Error::Bus)?;
+        let dimming = *self.dimming();
+        self.set_dimming(dimming).map_err(|_| Error::Bus)?;
+        let display = *self.display();
+        self.set_display(display).map_err(|_| Error::Bus)
+    }
+
+    /// Whether the system oscillator is off.
+    fn is_asleep(&self) -> bool {
+        !self.oscillator().contains(Oscillator::ON)
+    }
+}
+
+/// What to do with the display after a change of activity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdleEvent {
+    /// There has been no activity for the timeout, put the display in standby.
+    Sleep,
+    /// There is activity again, wake the display.
+    Wake,
+}
+
+/// Policy blanking the display after a period of inactivity.
+///
+/// The policy is driven by a millisecond clock, it keeps no clock of its own.
+/// Wrapping of the clock is handled, as long as the timeout is below half its range.
+///
+/// # Examples
+///
+/// ```
+/// use ht16k33::i2c_mock::I2cMock;
+/// use ht16k33::HT16K33;
+/// use adafruit_7segment::{IdleEvent, IdleTimeout, PowerManagement};
+///
+/// // Create an I2C device.
+/// let mut i2c = I2cMock::new();
+///
+/// // The I2C device address.
+/// const DISP_I2C_ADDR: u8 = 112;
+///
+/// let mut ht16k33 = HT16K33::new(i2c, DISP_I2C_ADDR);
+/// ht16k33.initialize().expect("Failed to initialize ht16k33");
+///
+/// // Blank the display after 30 seconds without a button press.
+/// let mut idle = IdleTimeout::new(30_000, 0);
+///
+/// # let (now_ms, button_pressed) = (31_000, false);
+/// let event = if button_pressed {
+///     idle.activity(now_ms)
+/// } else {
+///     idle.poll(now_ms)
+/// };
+/// match event {
+///     Some(IdleEvent::Sleep) => ht16k33.sleep().expect("Could not put the display in standby!"),
+///     Some(IdleEvent::Wake) => ht16k33.wake().expect("Could not wake the display!"),
+///     None => {}
+/// }
+/// assert!(ht16k33.is_asleep());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IdleTimeout {
+    timeout_ms: u32,
+    last_activity_ms: u32,
+    idle: bool,
+}
+
+impl IdleTimeout {
+    /// Create a policy with a timeout of `timeout_ms` milliseconds, counting from `now_ms`.
+    pub fn new(timeout_ms: u32, now_ms: u32) -> Self {
+        IdleTimeout {
+            timeout_ms,
+            last_activity_ms: now_ms,
+            idle: false,
+        }
+    }
+
+    /// Whether the timeout has passed without activity.
+    pub fn is_idle(&self) -> bool {
+        self.idle
+    }
+
+    /// Record activity, like a button press or new data to show.
+    /// Returns `IdleEvent::Wake` if the display was blanked.
+    ///
+    /// # Arguments
+    ///
+    /// * `now_ms` - The current time in milliseconds.
+    pub fn activity(&mut self, now_ms: u32) -> Option<IdleEvent> {
+        self.last_activity_ms = now_ms;
+        if self.idle {
+            self.idle = false;
+            Some(IdleEvent::Wake)
+        } else {
+            None
+        }
+    }
+
+    /// Check the timeout, call regularly.
+    /// Returns `IdleEvent::Sleep` once when the timeout passes without activity.
+    ///
+    /// # Arguments
+    ///
+    /// * `now_ms` - The current time in milliseconds.
+    pub fn poll(&mut self, now_ms: u32) -> Option<IdleEvent> {
+        if !self.idle && now_ms.wrapping_sub(self.last_activity_ms) >= self.timeout_ms {
+            self.idle = true;
+            Some(IdleEvent::Sleep)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use self::std::cell::RefCell;
+    use self::std::string::{String, ToString};
+    use super::*;
+    use crate::test_support::SharedBus;
+    use crate::{DecodedBuffer, Index, SevenSegment, Simulator};
+    use ht16k33::Display;
+
+    fn text(simulator: &RefCell<Simulator>) -> String {
+        DecodedBuffer::new(simulator.borrow().display_buffer()).to_string()
+    }
+
+    #[test]
+    fn sleep_and_wake() {
+        let simulator = RefCell::new(Simulator::new(0x70));
+        let mut ht16k33 = HT16K33::new(SharedBus(&simulator), 0x70);
+        ht16k33.initialize().unwrap();
+        ht16k33.set_display(Display::TWO_HZ).unwrap();
+        ht16k33.update_buffer_with_digit(Index::One, 1);
+        ht16k33.write_display_buffer().unwrap();
+
+        ht16k33.sleep().unwrap();
+        assert!(ht16k33.is_asleep());
+        assert!(!simulator.borrow().is_lit());
+        // Updates while asleep stay in the buffer.
+        ht16k33.update_buffer_with_digit(Index::Four, 4);
+        assert_eq!(text(&simulator), "1   ");
+
+        // Waking sends the updates made while asleep.
+        ht16k33.wake().unwrap();
+        assert!(!ht16k33.is_asleep());
+        assert!(simulator.borrow().is_lit());
+        assert_eq!(simulator.borrow().display(), Display::TWO_HZ);
+        assert_eq!(text(&simulator), "1  4");
+    }
+
+    #[test]
+    fn idle_timeout() {
+        let mut idle = IdleTimeout::new(1000, 0);
+        assert_eq!(idle.poll(999), None);
+        assert_eq!(idle.activity(500), None);
+        assert_eq!(idle.poll(1400), None);
+        assert_eq!(idle.poll(1500), Some(IdleEvent::Sleep));
+        assert!(idle.is_idle());
+        assert_eq!(idle.poll(5000), None);
+        assert_eq!(idle.activity(6000), Some(IdleEvent::Wake));
+        assert_eq!(idle.activity(6100), None);
+
+        // Across a wrap of the clock.
+        let mut idle = IdleTimeout::new(1000, u32::MAX - 100);
+        assert_eq!(idle.poll(800), None);
+        assert_eq!(idle.poll(900), Some(IdleEvent::Sleep));
+    }
+}
@@ -0,0 +1,292 @@
+// Copyright (c) 2020 Karl Thorén <karl.h.thoren@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Regions of adjacent digits, for showing independent values side by side.
+
+use crate::number::{self, Number};
+use crate::{Error, FloatFormat, Index, SevenSegment, Unit};
+
+/// A run of adjacent digits that values are formatted into.
+///
+/// The `update_buffer_with_*` methods of [`SevenSegment`] use all digits from their index
+/// to the end of the display. Formatting through a region instead only changes the digits
+/// of the region, and their dots, so values in other regions are kept.
+/// Values are right aligned in the region and the digits before them are blanked.
+///
+/// # Examples
+///
+/// ```
+/// use adafruit_7segment::{Frame, Index, Region};
+///
+/// let mut frame = Frame::new();
+///
+/// let code = Region::new(Index::One, 2).unwrap();
+/// let value = Region::new(Index::Three, 2).unwrap();
+///
+/// // Display reads "E4 7".
+/// code.update_with_text(&mut frame, "E4").unwrap();
+/// value.update_with_float(&mut frame, 7.0, 0, 10).unwrap();
+///
+/// // Only changes the last two digits, display reads "E412".
+/// value.update_with_float(&mut frame, 12.0, 0, 10).unwrap();
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    start: u8,
+    end: u8,
+}
+
+impl Region {
+    /// Create a region of `width` digits starting at `start`.
+    /// Returns `Error::InsufficientDigits` if the region is empty or does not fit on the display.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - The first digit of the region.
+    /// * `width` - Number of digits in the region.
+    pub fn new(start: Index, width: u8) -> Result<Self, Error> {
+        let start = u8::from(start);
+        if width == 0 || width > 4 - start {
+            return Err(Error::InsufficientDigits);
+        }
+        Ok(Region {
+            start,
+            end: start + width,
+        })
+    }
+
+    /// The whole display.
+    pub fn all() -> Self {
+        Region { start: 0, end: 4 }
+    }
+
+    /// The first digit of the region.
+    pub fn start(&self) -> Index {
+        Index::from(self.start)
+    }
+
+    /// Number of digits in the region.
+    pub fn width(&self) -> u8 {
+        self.end - self.start
+    }
+
+    /// Whether the digit is in the region.
+    pub fn contains(&self, index: Index) -> bool {
+        (self.start..self.end).contains(&u8::from(index))
+    }
+
+    /// Turn off all segments and dots of the region.
+    pub fn clear<S, E>(&self, display: &mut S)
+    where
+        S: SevenSegment<E> + ?Sized,
+    {
+        for pos in self.start..self.end {
+            display.update_buffer_with_segments(Index::from(pos), 0);
+        }
+    }
+
+    /// Update the region with text, right aligned. Limited to the ascii chars of
+    /// [`SevenSegment::update_buffer_with_char`].
+    ///
+    /// # Arguments
+    ///
+    /// * `display` - The display to draw on.
+    /// * `text` - Text of at most the width of the region.
+    pub fn update_with_text<S, E>(&self, display: &mut S, text: &str) -> Result<(), Error>
+    where
+        S: SevenSegment<E> + ?Sized,
+    {
+        number::update_buffer_with_text(display, self.start, self.end, text.as_bytes())
+    }
+
+    /// Update the region with a formatted float, like [`SevenSegment::update_buffer_with_float`].
+    ///
+    /// # Arguments
+    ///
+    /// * `display` - The display to draw on.
+    /// * `value` - float value.
+    /// * `fractional_digits` - Number of fractional digits.
+    /// * `base` - Base to use.
+    pub fn update_with_float<S, E>(
+        &self,
+        display: &mut S,
+        value: f32,
+        fractional_digits: u8,
+        base: u8,
+    ) -> Result<(), Error>
+    where
+        S: SevenSegment<E> + ?Sized,
+    {
+        let format = FloatFormat::new(fractional_digits, base);
+        self.update_with_number(display, value.into(), &format)
+    }
+
+    /// Update the region with a float formatted as described by `format`,
+    /// like [`SevenSegment::update_buffer_with_formatted_float`].
+    ///
+    /// # Arguments
+    ///
+    /// * `display` - The display to draw on.
+    /// * `value` - double value, an `f32` converts without loss.
+    /// * `format` - Formatting options.
+    pub fn update_with_formatted_float<S, E>(
+        &self,
+        display: &mut S,
+        value: f64,
+        format: &FloatFormat,
+    ) -> Result<(), Error>
+    where
+        S: SevenSegment<E> + ?Sized,
+    {
+        self.update_with_number(display, value.into(), format)
+    }
+
+    /// Update the region with a fixed-point value, `value / 10^scale_digits`,
+    /// like [`SevenSegment::update_buffer_with_fixed`].
+    ///
+    /// # Arguments
+    ///
+    /// * `display` - The display to draw on.
+    /// * `value` - The fixed-point value.
+    /// * `scale_digits` - Number of decimal digits of the value after the point.
+    pub fn update_with_fixed<S, E>(
+        &self,
+        display: &mut S,
+        value: i32,
+        scale_digits: u8,
+    ) -> Result<(), Error>
+    where
+        S: SevenSegment<E> + ?Sized,
+    {
+        let format = FloatFormat::new(scale_digits, 10);
+        self.update_with_formatted_fixed(display, value, scale_digits, &format)
+    }
+
+    /// Update the region with a fixed-point value formatted as described by `format`,
+    /// like [`SevenSegment::update_buffer_with_formatted_fixed`].
+    ///
+    /// # Arguments
+    ///
+    /// * `display` - The display to draw on.
+    /// * `value` - The fixed-point value.
+    /// * `scale_digits` - Number of decimal digits of the value after the point.
+    /// * `format` - Formatting options.
+    pub fn update_with_formatted_fixed<S, E>(
+        &self,
+        display: &mut S,
+        value: i32,
+        scale_digits: u8,
+        format: &FloatFormat,
+    ) -> Result<(), Error>
+    where
+        S: SevenSegment<E> + ?Sized,
+    {
+        self.update_with_number(display, Number::fixed(value, scale_digits), format)
+    }
+
+    /// Update the region with a measurement followed by its unit,
+    /// like [`SevenSegment::update_buffer_with_measurement`].
+    ///
+    /// # Arguments
+    ///
+    /// * `display` - The display to draw on.
+    /// * `value` - The measured value.
+    /// * `unit` - The unit, taking up the last digits of the region.
+    pub fn update_with_measurement<S, E>(
+        &self,
+        display: &mut S,
+        value: f64,
+        unit: Unit,
+    ) -> Result<(), Error>
+    where
+        S: SevenSegment<E> + ?Sized,
+    {
+        let format = FloatFormat::auto(10).unit(unit);
+        self.update_with_formatted_float(display, value, &format)
+    }
+
+    fn update_with_number<S, E>(
+        &self,
+        display: &mut S,
+        number: Number,
+        format: &FloatFormat,
+    ) -> Result<(), Error>
+    where
+        S: SevenSegment<E> + ?Sized,
+    {
+        number::update_buffer_with_number(display, self.start, self.end, number, format)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use self::std::string::{String, ToString};
+    use super::*;
+    use crate::{DecodedBuffer, Frame, Overflow};
+
+    fn text(frame: &Frame) -> String {
+        DecodedBuffer::new(frame.display_buffer()).to_string()
+    }
+
+    #[test]
+    fn confined_to_region() {
+        let mut frame = Frame::new();
+        let left = Region::new(Index::One, 2).unwrap();
+        let right = Region::new(Index::Three, 2).unwrap();
+        frame.set_overflow(Overflow::Dashes);
+
+        left.update_with_text(&mut frame, "A").unwrap();
+        right.update_with_float(&mut frame, 4.5, 1, 10).unwrap();
+        assert_eq!(text(&frame), " A4.5");
+
+        left.update_with_fixed(&mut frame, -12, 1).unwrap();
+        assert_eq!(text(&frame), "-14.5");
+
+        right.update_with_float(&mut frame, 123.0, 0, 10).unwrap();
+        assert_eq!(text(&frame), "-1--");
+
+        left.clear(&mut frame);
+        right
+            .update_with_formatted_float(&mut frame, 7.0, &FloatFormat::new(0, 10))
+            .unwrap();
+        assert_eq!(text(&frame), "   7");
+        assert!(left.update_with_text(&mut frame, "ABC").is_err());
+    }
+
+    #[test]
+    fn measurement() {
+        let mut frame = Frame::new();
+        Region::new(Index::Two, 3)
+            .unwrap()
+            .update_with_measurement(&mut frame, 9.46, Unit::Percent)
+            .unwrap();
+        assert_eq!(text(&frame), " 9°o");
+    }
+
+    #[test]
+    fn bounds() {
+        let region = Region::new(Index::Two, 2).unwrap();
+        assert!(region.start() == Index::Two);
+        assert_eq!(region.width(), 2);
+        assert!(region.contains(Index::Three));
+        assert!(!region.contains(Index::Four));
+        assert_eq!(Region::all().width(), 4);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(matches!(
+            Region::new(Index::Three, 3),
+            Err(Error::InsufficientDigits)
+        ));
+        assert!(Region::new(Index::One, 0).is_err());
+        assert!(Region::new(Index::Four, 1).is_ok());
+        assert!(Region::new(Index::Two, 255).is_err());
+    }
+}
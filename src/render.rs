@@ -0,0 +1,136 @@
+// Copyright (c) 2020 Karl Thorén <karl.h.thoren@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Rendering of the display buffer as text art, for tests and logs on hosts without hardware.
+
+use ht16k33::{DisplayData, ROWS_SIZE};
+
+use crate::{DecodedBuffer, Index};
+
+/// Characters used to draw the display.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Style {
+    /// `_` and `|` for the segments, `.` for the dots and `:` for the colon.
+    Ascii,
+    /// Box drawing characters for the segments, `•` for the dots and `:` for the colon.
+    Unicode,
+}
+
+impl Style {
+    /// Characters for horizontal segments, vertical segments and dots.
+    fn chars(self) -> (char, char, char) {
+        match self {
+            Style::Ascii => ('_', '|', '.'),
+            Style::Unicode => ('━', '┃', '•'),
+        }
+    }
+}
+
+/// Draw the display buffer as three lines of text art.
+///
+/// Each digit is three characters wide followed by its dot, the colon has its own
+/// column between the second and third digit. Lines end with a newline.
+///
+/// # Arguments
+///
+/// * `buffer` - The display buffer, from the `HT16K33` or a `Frame`.
+/// * `style` - Characters used to draw the display.
+///
+/// # Examples
+///
+/// ```
+/// use adafruit_7segment::{render, Frame, Index, SevenSegment, Style};
+///
+/// let mut frame = Frame::new();
+/// frame.update_buffer_with_float(Index::One, 12.5, 1, 10).unwrap();
+///
+/// print!("{}", render(frame.display_buffer(), Style::Ascii));
+/// ```
+pub fn render(buffer: &[DisplayData; ROWS_SIZE], style: Style) -> String {
+    let decoded = DecodedBuffer::new(buffer);
+    let (horizontal, vertical, dot) = style.chars();
+    let segment = |segments: u8, bit: u8, c: char| {
+        if segments & (1 << bit) != 0 {
+            c
+        } else {
+            ' '
+        }
+    };
+
+    let mut lines = [String::new(), String::new(), String::new()];
+    for i in 0..4 {
+        let index = Index::from(i);
+        let s = decoded.segments(index);
+        if index == Index::Three {
+            lines[0].push(' ');
+            lines[1].push(if decoded.colon() { ':' } else { ' ' });
+            lines[2].push(' ');
+        }
+        // Segments A to G are bit 0 to 6.
+        lines[0].extend(&[' ', segment(s, 0, horizontal), ' ', ' ']);
+        lines[1].extend(&[
+            segment(s, 5, vertical),
+            segment(s, 6, horizontal),
+            segment(s, 1, vertical),
+            ' ',
+        ]);
+        lines[2].extend(&[
+            segment(s, 4, vertical),
+            segment(s, 3, horizontal),
+            segment(s, 2, vertical),
+            if decoded.dot(index) { dot } else { ' ' },
+        ]);
+    }
+
+    let mut text = String::new();
+    for line in lines.iter() {
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Frame, SevenSegment};
+
+    #[test]
+    fn render_ascii() {
+        let mut frame = Frame::new();
+        frame
+            .update_buffer_with_float(Index::One, -9.8, 1, 10)
+            .unwrap();
+        frame.update_buffer_with_colon(true);
+
+        assert_eq!(
+            render(frame.display_buffer(), Style::Ascii),
+            concat!(
+                "          _   _\n",
+                "     _  :|_| |_|\n",
+                "          _|.|_|\n",
+            )
+        );
+    }
+
+    #[test]
+    fn render_unicode() {
+        let mut frame = Frame::new();
+        frame.update_buffer_with_hex(0x1A7F);
+        frame.update_buffer_with_dot(Index::Four, true);
+
+        assert_eq!(
+            render(frame.display_buffer(), Style::Unicode),
+            concat!(
+                "     ━    ━   ━\n",
+                "  ┃ ┃━┃    ┃ ┃━\n",
+                "  ┃ ┃ ┃    ┃ ┃  •\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,457 @@
+// Copyright (c) 2020 Karl Thorén <karl.h.thoren@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A display shared between tasks or interrupt handlers, each owning a part of it.
+
+use core::cell::RefCell;
+use core::ops::BitOr;
+
+use critical_section::Mutex;
+use embedded_hal::blocking::i2c::Write;
+use ht16k33::Dimming;
+
+use crate::{digit_position, Frame, Index, Overflow, Region, COLON_BIT};
+
+/// A set of digits and the colon, the parts of the display a handle may draw on.
+///
+/// # Examples
+///
+/// ```
+/// use adafruit_7segment::{Index, Parts};
+///
+/// let clock = Parts::digits(Index::One, Index::Two) | Parts::COLON;
+/// assert!(clock.contains(Parts::digit(Index::Two)));
+/// assert!(!clock.contains(Parts::digit(Index::Three)));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Parts(u8);
+
+impl Parts {
+    /// The colon.
+    pub const COLON: Parts = Parts(1 << 4);
+
+    /// No parts.
+    pub const fn empty() -> Self {
+        Parts(0)
+    }
+
+    /// One digit, with its dot.
+    pub fn digit(index: Index) -> Self {
+        Parts(1 << u8::from(index))
+    }
+
+    /// The digits from `first` to `last`, both included, with their dots.
+    pub fn digits(first: Index, last: Index) -> Self {
+        (u8::from(first)..=u8::from(last)).fold(Parts::empty(), |parts, index| {
+            parts | Parts::digit(Index::from(index))
+        })
+    }
+
+    /// Whether all of `other` is in these parts.
+    pub fn contains(self, other: Parts) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Whether any of `other` is in these parts.
+    pub fn intersects(self, other: Parts) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl BitOr for Parts {
+    type Output = Parts;
+
+    fn bitor(self, other: Parts) -> Parts {
+        Parts(self.0 | other.0)
+    }
+}
+
+impl From<Region> for Parts {
+    /// The digits of a region.
+    fn from(region: Region) -> Parts {
+        let last = u8::from(region.start()) + region.width() - 1;
+        Parts::digits(region.start(), Index::from(last))
+    }
+}
+
+struct State {
+    frame: Frame,
+    claimed: Parts,
+    // The parts of the handles that asked for blinking.
+    blinking: Parts,
+}
+
+impl State {
+    /// Record the blink request of a handle, the display blinks while any handle asks for it.
+    fn request_blink(&mut self, parts: Parts, blink: bool) {
+        self.blinking = if blink {
+            self.blinking | parts
+        } else {
+            Parts(self.blinking.0 & !parts.0)
+        };
+        self.frame.request_blink(self.blinking != Parts::empty());
+    }
+}
+
+/// A display buffer shared between tasks, each drawing only on the parts it owns.
+///
+/// The frame is kept in a `critical_section::Mutex`, so the display can be a `static`
+/// used from RTIC tasks, interrupt handlers or threads. A task claims parts of the display
+/// and gets a [`DisplayHandle`], parts can only be claimed by one handle at a time.
+/// Drawing through a handle changes only its parts, the drawing of the other handles is
+/// kept, and a flush sends the merged buffer.
+///
+/// The overflow policy is display-wide, set with [`SharedDisplay::set_overflow`].
+/// The display blinks while any handle asks for it with `Overflow::ClampBlink`,
+/// the blinking is sent by the next flush.
+///
+/// Needs the `critical-section` feature, and a critical section implementation for the target.
+///
+/// # Examples
+///
+/// ```
+/// use ht16k33::i2c_mock::I2cMock;
+/// use adafruit_7segment::{Index, Parts, SevenSegment, SharedDisplay};
+///
+/// static DISPLAY: SharedDisplay = SharedDisplay::new();
+///
+/// // Create an I2C device.
+/// let mut i2c = I2cMock::new();
+///
+/// // The I2C device address.
+/// const DISP_I2C_ADDR: u8 = 112;
+///
+/// // Given to the user interface task and the alarm task.
+/// let mut ui = DISPLAY.claim(Parts::digits(Index::One, Index::Two)).unwrap();
+/// let mut alarm = DISPLAY.claim(Parts::digits(Index::Three, Index::Four) | Parts::COLON).unwrap();
+/// assert!(DISPLAY.claim(Parts::digit(Index::One)).is_none());
+///
+/// ui.draw(|frame| {
+///     frame.update_buffer_with_digit(Index::One, 4);
+///     frame.update_buffer_with_dot(Index::One, true);
+///     frame.update_buffer_with_digit(Index::Two, 2);
+/// });
+/// alarm.draw(|frame| {
+///     frame.update_buffer_with_colon(true);
+///     frame.update_buffer_with_digit(Index::Three, 0);
+///     frame.update_buffer_with_digit(Index::Four, 7);
+/// });
+///
+/// // For example from a timer interrupt. Display reads "4.2:07".
+/// DISPLAY.flush(&mut i2c, DISP_I2C_ADDR).expect("Could not write the display RAM!");
+/// ```
+pub struct SharedDisplay {
+    state: Mutex<RefCell<State>>,
+}
+
+impl Default for SharedDisplay {
+    fn default() -> Self {
+        SharedDisplay::new()
+    }
+}
+
+impl SharedDisplay {
+    /// Create a shared display with an empty frame and no parts claimed.
+    pub const fn new() -> Self {
+        SharedDisplay {
+            state: Mutex::new(RefCell::new(State {
+                frame: Frame::new(),
+                claimed: Parts::empty(),
+                blinking: Parts::empty(),
+            })),
+        }
+    }
+
+    /// Claim parts of the display, `None` if any of them is claimed by another handle.
+    /// The parts are released when the handle is dropped.
+    pub fn claim(&self, parts: Parts) -> Option<DisplayHandle<'_>> {
+        critical_section::with(|cs| {
+            let mut state = self.state.borrow_ref_mut(cs);
+            if state.claimed.intersects(parts) {
+                None
+            } else {
+                state.claimed = state.claimed | parts;
+                Some(DisplayHandle {
+                    display: self,
+                    parts,
+                })
+            }
+        })
+    }
+
+    /// A copy of the merged frame.
+    pub fn frame(&self) -> Frame {
+        critical_section::with(|cs| self.state.borrow_ref(cs).frame)
+    }
+
+    /// Set the overflow policy of all handles, see [`Frame::set_overflow`].
+    pub fn set_overflow(&self, overflow: Overflow) {
+        critical_section::with(|cs| self.state.borrow_ref_mut(cs).frame.set_overflow(overflow));
+    }
+
+    /// Turn on the oscillator and the display, blinking if a handle asks for it,
+    /// and set the dimming, see [`Frame::setup`].
+    ///
+    /// # Arguments
+    ///
+    /// * `i2c` - The I2C bus the display is on, only borrowed during the setup.
+    /// * `address` - The I2C address of the display.
+    /// * `dimming` - The dimming level to set.
+    pub fn setup<I2C, E>(&self, i2c: &mut I2C, address: u8, dimming: Dimming) -> Result<(), E>
+    where
+        I2C: Write<Error = E>,
+    {
+        let mut snapshot = critical_section::with(|cs| {
+            let mut state = self.state.borrow_ref_mut(cs);
+            let snapshot = state.frame;
+            let (rows, _) = state.frame.take_dirty();
+            state.frame.mark_dirty(rows, false);
+            snapshot
+        });
+        let result = snapshot.setup(i2c, address, dimming);
+        if result.is_err() {
+            critical_section::with(|cs| self.state.borrow_ref_mut(cs).frame.mark_dirty(0, true));
+        }
+        result
+    }
+
+    /// Send the rows changed since the last flush to the display, see [`Frame::flush`].
+    ///
+    /// The frame is copied in a critical section and sent outside of it, so handles can
+    /// draw during the transfer. Their changes are sent by the next flush.
+    ///
+    /// # Arguments
+    ///
+    /// * `i2c` - The I2C bus the display is on, only borrowed during the flush.
+    /// * `address` - The I2C address of the display.
+    pub fn flush<I2C, E>(&self, i2c: &mut I2C, address: u8) -> Result<(), E>
+    where
+        I2C: Write<Error = E>,
+    {
+        let mut snapshot = critical_section::with(|cs| {
+            let mut state = self.state.borrow_ref_mut(cs);
+            let snapshot = state.frame;
+            state.frame.take_dirty();
+            snapshot
+        });
+        let result = snapshot.flush(i2c, address);
+        if result.is_err() {
+            let (rows, blink) = snapshot.take_dirty();
+            critical_section::with(|cs| {
+                self.state.borrow_ref_mut(cs).frame.mark_dirty(rows, blink)
+            });
+        }
+        result
+    }
+}
+
+/// The right to draw on some parts of a [`SharedDisplay`].
+pub struct DisplayHandle<'a> {
+    display: &'a SharedDisplay,
+    parts: Parts,
+}
+
+impl DisplayHandle<'_> {
+    /// The parts this handle draws on.
+    pub fn parts(&self) -> Parts {
+        self.parts
+    }
+
+    /// Draw on a copy of the merged frame, then merge the parts owned by this handle back.
+    /// Drawing on other parts has no effect. The drawing is done outside the critical section.
+    ///
+    /// The blinking asked for while drawing, with `Overflow::ClampBlink`, is kept as the
+    /// request of this handle. Changes of the overflow policy are not kept, use
+    /// [`SharedDisplay::set_overflow`].
+    ///
+    /// # Arguments
+    ///
+    /// * `draw` - Draws on the frame with the [`SevenSegment`](crate::SevenSegment) methods.
+    pub fn draw<R>(&mut self, draw: impl FnOnce(&mut Frame) -> R) -> R {
+        let mut scratch = critical_section::with(|cs| {
+            let state = self.display.state.borrow_ref(cs);
+            let mut scratch = state.frame;
+            scratch.request_blink(state.blinking.intersects(self.parts));
+            scratch
+        });
+        let result = draw(&mut scratch);
+        critical_section::with(|cs| {
+            let mut state = self.display.state.borrow_ref_mut(cs);
+            state.request_blink(self.parts, scratch.blink());
+            let frame = &mut state.frame;
+            for index in 0..4 {
+                let index = Index::from(index);
+                if self.parts.contains(Parts::digit(index)) {
+                    let row = usize::from(digit_position(index) * 2);
+                    frame.copy_bits_from(&scratch, row, 0xFF);
+                }
+            }
+            if self.parts.contains(Parts::COLON) {
+                frame.copy_bits_from(&scratch, 4, 1 << COLON_BIT);
+            }
+        });
+        result
+    }
+}
+
+impl Drop for DisplayHandle<'_> {
+    fn drop(&mut self) {
+        critical_section::with(|cs| {
+            let mut state = self.display.state.borrow_ref_mut(cs);
+            state.claimed = Parts(state.claimed.0 & !self.parts.0);
+            state.request_blink(self.parts, false);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use self::std::string::ToString;
+    use self::std::thread;
+    use self::std::vec;
+    use super::*;
+    use crate::{DecodedBuffer, SevenSegment};
+    use embedded_hal_mock::i2c::{Mock as I2cMock, Transaction};
+
+    fn text(display: &SharedDisplay) -> std::string::String {
+        DecodedBuffer::new(display.frame().display_buffer()).to_string()
+    }
+
+    #[test]
+    fn claims() {
+        let display = SharedDisplay::new();
+        let left = display.claim(Parts::digits(Index::One, Index::Two));
+        assert!(left.is_some());
+        assert!(display.claim(Parts::digit(Index::Two)).is_none());
+        let right = display.claim(Parts::digits(Index::Three, Index::Four) | Parts::COLON);
+        assert!(right.is_some());
+        assert!(display
+            .claim(Region::new(Index::Two, 2).unwrap().into())
+            .is_none());
+        drop(left);
+        assert!(display.claim(Parts::digit(Index::Two)).is_some());
+    }
+
+    #[test]
+    fn draw_merges_owned_parts() {
+        let display = SharedDisplay::new();
+        let mut left = display
+            .claim(Parts::digits(Index::One, Index::Two))
+            .unwrap();
+        let mut right = display
+            .claim(Parts::digits(Index::Three, Index::Four) | Parts::COLON)
+            .unwrap();
+
+        left.draw(|frame| {
+            frame.update_buffer_with_digit(Index::One, 1);
+            frame.update_buffer_with_dot(Index::One, true);
+            frame.update_buffer_with_digit(Index::Two, 5);
+        });
+        right.draw(|frame| {
+            frame.update_buffer_with_colon(true);
+            frame.update_buffer_with_digit(Index::Three, 4);
+            frame.update_buffer_with_digit(Index::Four, 2);
+        });
+        assert_eq!(text(&display), "1.5:42");
+
+        // Formatting draws on the whole display, only the owned digits are kept.
+        right
+            .draw(|frame| frame.update_buffer_with_float(Index::One, 99.9, 1, 10))
+            .unwrap();
+        assert_eq!(text(&display), "1.5:9.9");
+    }
+
+    #[test]
+    fn threads() {
+        let display = SharedDisplay::new();
+        let mut handles: vec::Vec<_> = (0..4)
+            .map(|index| display.claim(Parts::digit(Index::from(index))).unwrap())
+            .collect();
+        thread::scope(|scope| {
+            for (value, handle) in handles.iter_mut().enumerate() {
+                scope.spawn(move || {
+                    for _ in 0..100 {
+                        let index = Index::from(value as u8);
+                        handle.draw(|frame| frame.update_buffer_with_digit(index, value as u8));
+                    }
+                });
+            }
+        });
+        assert_eq!(text(&display), "0123");
+    }
+
+    #[test]
+    fn flush() {
+        let mut all_rows = vec![0x00];
+        all_rows.extend([0; ht16k33::ROWS_SIZE].iter().cloned());
+        all_rows[1] = 0b0000_0110;
+        let expectations = [
+            Transaction::write(0x70, all_rows),
+            Transaction::write(0x70, vec![0x04, 0b0000_0010]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let display = SharedDisplay::new();
+        let mut left = display.claim(Parts::digit(Index::One)).unwrap();
+        let mut colon = display.claim(Parts::COLON).unwrap();
+
+        left.draw(|frame| frame.update_buffer_with_digit(Index::One, 1));
+        display.flush(&mut i2c, 0x70).unwrap();
+        colon.draw(|frame| frame.update_buffer_with_colon(true));
+        display.flush(&mut i2c, 0x70).unwrap();
+        display.flush(&mut i2c, 0x70).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn blink_requests() {
+        let mut all_rows = vec![0x00];
+        all_rows.extend([0; ht16k33::ROWS_SIZE].iter().cloned());
+        let expectations = [
+            Transaction::write(0x70, vec![0x21]),
+            Transaction::write(0x70, vec![0xEF]),
+            Transaction::write(0x70, vec![0x81]),
+            Transaction::write(0x70, all_rows),
+            Transaction::write(0x70, vec![0x08, 0b0110_1111]),
+            Transaction::write(0x70, vec![0x85]),
+            Transaction::write(0x70, vec![0x08, 0b0110_0110]),
+            Transaction::write(0x70, vec![0x81]),
+        ];
+        let mut i2c = I2cMock::new(&expectations);
+        let display = SharedDisplay::new();
+        display.set_overflow(Overflow::ClampBlink);
+        display
+            .setup(&mut i2c, 0x70, Dimming::BRIGHTNESS_MAX)
+            .unwrap();
+        display.flush(&mut i2c, 0x70).unwrap();
+
+        let mut left = display.claim(Parts::digit(Index::One)).unwrap();
+        let mut right = display.claim(Parts::digit(Index::Four)).unwrap();
+        right
+            .draw(|frame| frame.update_buffer_with_fixed(Index::Four, 12, 0))
+            .unwrap();
+        // Another handle drawing a value that fits does not stop the blinking.
+        left.draw(|frame| frame.update_buffer_with_segments(Index::One, 0));
+        assert!(display.frame().blink());
+        display.flush(&mut i2c, 0x70).unwrap();
+
+        right
+            .draw(|frame| frame.update_buffer_with_fixed(Index::Four, 4, 0))
+            .unwrap();
+        assert!(!display.frame().blink());
+        display.flush(&mut i2c, 0x70).unwrap();
+
+        // Dropping a blinking handle stops its blinking.
+        right
+            .draw(|frame| frame.update_buffer_with_fixed(Index::Four, 12, 0))
+            .unwrap();
+        drop(right);
+        assert!(!display.frame().blink());
+        i2c.done();
+    }
+}
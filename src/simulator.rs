@@ -0,0 +1,211 @@
+// Copyright (c) 2020 Karl Thorén <karl.h.thoren@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A simulated HT16K33 on an I2C bus, for tests on hosts without hardware.
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use ht16k33::{Dimming, Display, DisplayData, Oscillator, ROWS_SIZE};
+
+/// Errors returned by the simulated I2C bus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimulatorError {
+    /// No device answers on the address.
+    Nack,
+    /// The command byte is not one the HT16K33 knows.
+    InvalidCommand(u8),
+}
+
+/// A simulated HT16K33 that interprets the commands sent to it.
+///
+/// Implements the I2C traits, so it can be handed to `HT16K33::new` or a
+/// [`Frame::flush`](crate::Frame::flush) in place of a real bus. System setup,
+/// display setup, dimming and display RAM writes and reads are interpreted and the
+/// resulting state can be inspected, instead of listing the exact bytes expected on the bus.
+///
+/// # Examples
+///
+/// ```
+/// use ht16k33::{Dimming, Display, HT16K33};
+/// use adafruit_7segment::{Index, SevenSegment, Simulator};
+///
+/// // The I2C device address.
+/// const DISP_I2C_ADDR: u8 = 112;
+///
+/// let mut ht16k33 = HT16K33::new(Simulator::new(DISP_I2C_ADDR), DISP_I2C_ADDR);
+/// ht16k33.initialize().expect("Failed to initialize ht16k33");
+/// ht16k33.set_display(Display::ONE_HZ).expect("Could not turn on the display!");
+/// ht16k33.set_dimming(Dimming::BRIGHTNESS_5_16).expect("Could not set dimming!");
+/// ht16k33.update_buffer_with_float(Index::One, -3.14, 2, 10).expect("Failed to encode float to buffer!");
+/// ht16k33.write_display_buffer().expect("Could not write the display RAM!");
+///
+/// let simulator = ht16k33.destroy();
+/// assert_eq!(simulator.display(), Display::ONE_HZ);
+/// assert_eq!(simulator.dimming(), Dimming::BRIGHTNESS_5_16);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Simulator {
+    address: u8,
+    ram: [DisplayData; ROWS_SIZE],
+    oscillator: Oscillator,
+    display: Display,
+    dimming: Dimming,
+    // Display RAM address of the next data byte.
+    pointer: usize,
+}
+
+impl Simulator {
+    /// Create a simulated HT16K33 answering on `address`, in its power-on state.
+    pub fn new(address: u8) -> Self {
+        Simulator {
+            address,
+            ram: [DisplayData::empty(); ROWS_SIZE],
+            oscillator: Oscillator::OFF,
+            display: Display::OFF,
+            dimming: Dimming::BRIGHTNESS_MAX,
+            pointer: 0,
+        }
+    }
+
+    /// The display RAM.
+    pub fn display_buffer(&self) -> &[DisplayData; ROWS_SIZE] {
+        &self.ram
+    }
+
+    /// The oscillator state set by the last system setup command.
+    pub fn oscillator(&self) -> Oscillator {
+        self.oscillator
+    }
+
+    /// The display state set by the last display setup command, on or off and the blink rate.
+    pub fn display(&self) -> Display {
+        self.display
+    }
+
+    /// The brightness set by the last dimming command.
+    pub fn dimming(&self) -> Dimming {
+        self.dimming
+    }
+
+    /// Whether the LEDs are lit, which needs both the oscillator and the display on.
+    pub fn is_lit(&self) -> bool {
+        self.oscillator.contains(Oscillator::ON) && self.display.contains(Display::ON)
+    }
+
+    fn check_address(&self, address: u8) -> Result<(), SimulatorError> {
+        if address == self.address {
+            Ok(())
+        } else {
+            Err(SimulatorError::Nack)
+        }
+    }
+
+    fn command(&mut self, command: u8) -> Result<(), SimulatorError> {
+        match command {
+            0x00..=0x0F => self.pointer = usize::from(command & 0x0F),
+            0x20..=0x21 => self.oscillator = Oscillator::from_bits_truncate(command & 0x01),
+            0x80..=0x87 => self.display = Display::from_bits_truncate(command & 0x07),
+            0xE0..=0xEF => self.dimming = Dimming::from_bits_truncate(command & 0x0F),
+            // ROW/INT set, the 7-segment backpack does not use the key scan.
+            0xA0..=0xA3 => {}
+            _ => return Err(SimulatorError::InvalidCommand(command)),
+        }
+        Ok(())
+    }
+}
+
+impl Write for Simulator {
+    type Error = SimulatorError;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), SimulatorError> {
+        self.check_address(address)?;
+        let (&command, data) = match bytes.split_first() {
+            Some(split) => split,
+            None => return Ok(()),
+        };
+        self.command(command)?;
+        if command & 0xF0 == 0x00 {
+            // Display RAM write, the address wraps around.
+            for &value in data {
+                self.ram[self.pointer] = DisplayData::from_bits_truncate(value);
+                self.pointer = (self.pointer + 1) % ROWS_SIZE;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl WriteRead for Simulator {
+    type Error = SimulatorError;
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), SimulatorError> {
+        self.write(address, bytes)?;
+        for value in buffer.iter_mut() {
+            *value = self.ram[self.pointer].bits();
+            self.pointer = (self.pointer + 1) % ROWS_SIZE;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DecodedBuffer, Frame, Index, SevenSegment, Verification, Verify};
+    use ht16k33::HT16K33;
+
+    const ADDRESS: u8 = 0x70;
+
+    #[test]
+    fn ht16k33_commands() {
+        let mut ht16k33 = HT16K33::new(Simulator::new(ADDRESS), ADDRESS);
+        ht16k33.initialize().unwrap();
+        ht16k33.set_display(Display::TWO_HZ).unwrap();
+        ht16k33.set_dimming(Dimming::BRIGHTNESS_3_16).unwrap();
+        ht16k33
+            .update_buffer_with_float(Index::One, -3.25, 2, 10)
+            .unwrap();
+        ht16k33.write_display_buffer().unwrap();
+        assert_eq!(ht16k33.verify(false).unwrap(), Verification::Match);
+
+        let simulator = ht16k33.destroy();
+        assert!(simulator.is_lit());
+        assert_eq!(simulator.display(), Display::TWO_HZ);
+        assert_eq!(simulator.dimming(), Dimming::BRIGHTNESS_3_16);
+        let decoded = DecodedBuffer::new(simulator.display_buffer());
+        assert_eq!(decoded.segments(Index::One), 0b0100_0000);
+        assert!(decoded.dot(Index::Two));
+    }
+
+    #[test]
+    fn frame_flush_and_errors() {
+        let mut simulator = Simulator::new(ADDRESS);
+        let mut frame = Frame::new();
+        frame.update_buffer_with_digit(Index::Four, 7);
+        frame.flush(&mut simulator, ADDRESS).unwrap();
+        assert_eq!(simulator.display_buffer(), frame.display_buffer());
+        assert!(!simulator.is_lit());
+
+        // The RAM address wraps around.
+        simulator.write(ADDRESS, &[0x0F, 0x01, 0x02]).unwrap();
+        assert_eq!(simulator.display_buffer()[15].bits(), 0x01);
+        assert_eq!(simulator.display_buffer()[0].bits(), 0x02);
+
+        assert_eq!(simulator.write(0x71, &[0x21]), Err(SimulatorError::Nack));
+        for &command in &[0x40, 0x22, 0x88, 0x90, 0xA4, 0xF0] {
+            assert_eq!(
+                simulator.write(ADDRESS, &[command]),
+                Err(SimulatorError::InvalidCommand(command))
+            );
+        }
+    }
+}
@@ -0,0 +1,295 @@
+// Copyright (c) 2020 Karl Thorén <karl.h.thoren@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Export of the display buffer as an SVG image, for documentation and visual regression tests.
+
+use core::fmt::Write;
+
+use ht16k33::{DisplayData, ROWS_SIZE};
+
+use crate::{DecodedBuffer, Index};
+
+/// The 7-segment backpack variant to draw.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Board {
+    /// The 0.56" backpack, with a dot after every digit and a colon in the middle.
+    Small,
+    /// The 1.2" backpack, which also has a left colon and an upper dot.
+    Large,
+}
+
+/// Options for drawing the display as an SVG image.
+///
+/// # Examples
+///
+/// ```
+/// use adafruit_7segment::{Board, SvgOptions};
+///
+/// // Green segments, off segments faintly visible.
+/// let options = SvgOptions::new(Board::Small).color("#00ff00").ghosting(0.1);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SvgOptions {
+    board: Board,
+    color: String,
+    ghosting: f32,
+    digit_height: f32,
+}
+
+impl SvgOptions {
+    /// Create options with red segments, hidden off segments and a digit height
+    /// matching the board, 56 or 120 pixels.
+    pub fn new(board: Board) -> Self {
+        SvgOptions {
+            board,
+            color: String::from("#ff2000"),
+            ghosting: 0.,
+            digit_height: match board {
+                Board::Small => 56.,
+                Board::Large => 120.,
+            },
+        }
+    }
+
+    /// Set the color of lit segments, any SVG color like `red` or `#ff2000`.
+    /// Characters with a meaning in XML are escaped.
+    pub fn color(mut self, color: &str) -> Self {
+        self.color = escape(color);
+        self
+    }
+
+    /// Set the opacity of off segments, from 0 for hidden to 1 for as bright as lit segments.
+    pub fn ghosting(mut self, opacity: f32) -> Self {
+        self.ghosting = opacity.clamp(0., 1.);
+        self
+    }
+
+    /// Set the height of a digit in pixels, the rest of the image scales with it.
+    pub fn digit_height(mut self, height: f32) -> Self {
+        self.digit_height = height;
+        self
+    }
+}
+
+/// Escape text for use in an XML attribute value.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Draws shapes with the lit color or the ghosting.
+struct Canvas<'a> {
+    svg: String,
+    options: &'a SvgOptions,
+}
+
+impl<'a> Canvas<'a> {
+    /// Fill attributes for a lit or an off shape, `None` if the shape is not drawn.
+    fn fill(&self, on: bool) -> Option<String> {
+        if on {
+            Some(format!("fill=\"{}\"", self.options.color))
+        } else if self.options.ghosting > 0. {
+            Some(format!(
+                "fill=\"{}\" fill-opacity=\"{}\"",
+                self.options.color, self.options.ghosting
+            ))
+        } else {
+            None
+        }
+    }
+
+    fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, on: bool) {
+        if let Some(fill) = self.fill(on) {
+            // Writing to a string does not fail.
+            let _ = writeln!(
+                self.svg,
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\" {}/>",
+                x,
+                y,
+                width,
+                height,
+                width.min(height) / 2.,
+                fill
+            );
+        }
+    }
+
+    fn dot(&mut self, x: f32, y: f32, radius: f32, on: bool) {
+        if let Some(fill) = self.fill(on) {
+            let _ = writeln!(
+                self.svg,
+                "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" {}/>",
+                x, y, radius, fill
+            );
+        }
+    }
+}
+
+/// Draw the display buffer as an SVG image.
+///
+/// # Arguments
+///
+/// * `buffer` - The display buffer, from the `HT16K33` or a `Frame`.
+/// * `options` - Board variant, colors and size.
+///
+/// # Examples
+///
+/// ```
+/// use adafruit_7segment::{svg, Board, Frame, Index, SevenSegment, SvgOptions};
+///
+/// let mut frame = Frame::new();
+/// frame.update_buffer_with_float(Index::One, 12.5, 1, 10).unwrap();
+///
+/// let image = svg(frame.display_buffer(), &SvgOptions::new(Board::Small));
+/// assert!(image.starts_with("<svg"));
+/// ```
+pub fn svg(buffer: &[DisplayData; ROWS_SIZE], options: &SvgOptions) -> String {
+    let decoded = DecodedBuffer::new(buffer);
+    let h = options.digit_height;
+    let w = h * 0.55;
+    let t = h * 0.1;
+    let margin = h * 0.2;
+    // Room for the dot after each digit, and for the colon in the middle.
+    let digit_pitch = w + 2. * t;
+    let colon_width = 2. * t;
+    let width = 2. * margin + 4. * digit_pitch + colon_width;
+    let height = 2. * margin + h;
+
+    let mut canvas = Canvas {
+        svg: String::new(),
+        options,
+    };
+    let _ = writeln!(
+        canvas.svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+        width, height, width, height
+    );
+    let _ = writeln!(
+        canvas.svg,
+        "<rect width=\"{}\" height=\"{}\" fill=\"#101010\"/>",
+        width, height
+    );
+
+    let vertical = h / 2. - 1.5 * t;
+    for i in 0..4 {
+        let index = Index::from(i);
+        let s = decoded.segments(index);
+        let lit = |bit: u8| s & (1 << bit) != 0;
+        let mut x = margin + f32::from(i) * digit_pitch;
+        if index > Index::Two {
+            x += colon_width;
+        }
+        let y = margin;
+        // Segments A to G are bit 0 to 6.
+        canvas.rect(x + t, y, w - 2. * t, t, lit(0));
+        canvas.rect(x + w - t, y + t, t, vertical, lit(1));
+        canvas.rect(x + w - t, y + h / 2. + t / 2., t, vertical, lit(2));
+        canvas.rect(x + t, y + h - t, w - 2. * t, t, lit(3));
+        canvas.rect(x, y + h / 2. + t / 2., t, vertical, lit(4));
+        canvas.rect(x, y + t, t, vertical, lit(5));
+        canvas.rect(x + t, y + h / 2. - t / 2., w - 2. * t, t, lit(6));
+        canvas.dot(x + w + t, y + h - t / 2., t / 2., decoded.dot(index));
+    }
+
+    let colon_row = decoded.colon_row();
+    let colon_x = margin + 2. * digit_pitch + colon_width / 2. - t / 2.;
+    canvas.dot(colon_x, margin + h * 0.3, t / 2., decoded.colon());
+    canvas.dot(colon_x, margin + h * 0.7, t / 2., decoded.colon());
+    if options.board == Board::Large {
+        // Left colon and upper dot.
+        canvas.dot(margin / 2., margin + h * 0.3, t / 2., colon_row & 0x04 != 0);
+        canvas.dot(margin / 2., margin + h * 0.7, t / 2., colon_row & 0x08 != 0);
+        let upper_x = margin + 3. * digit_pitch + colon_width - t;
+        canvas.dot(upper_x, margin + t / 2., t / 2., colon_row & 0x10 != 0);
+    }
+
+    canvas.svg.push_str("</svg>\n");
+    canvas.svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Frame, SevenSegment};
+
+    #[test]
+    fn svg_lit_segments() {
+        let mut frame = Frame::new();
+        frame.update_buffer_with_digit(Index::One, 1);
+        frame.update_buffer_with_dot(Index::One, true);
+        frame.update_buffer_with_colon(true);
+
+        let options = SvgOptions::new(Board::Small).color("red");
+        let image = svg(frame.display_buffer(), &options);
+        assert!(image.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(image.ends_with("</svg>\n"));
+        // Segments B and C, the dot and the two dots of the colon.
+        assert_eq!(image.matches("<rect").count(), 3);
+        assert_eq!(image.matches("<circle").count(), 3);
+        assert_eq!(image.matches("fill=\"red\"").count(), 5);
+        assert!(!image.contains("fill-opacity"));
+    }
+
+    #[test]
+    fn svg_segment_fills() {
+        let mut frame = Frame::new();
+        frame.update_buffer_with_digit(Index::One, 1);
+
+        let options = SvgOptions::new(Board::Small)
+            .color("red")
+            .ghosting(0.2)
+            .digit_height(100.);
+        let image = svg(frame.display_buffer(), &options);
+        // Segment A of the first digit is off, segment B is lit.
+        assert!(image.contains(
+            "<rect x=\"30\" y=\"20\" width=\"35\" height=\"10\" rx=\"5\" fill=\"red\" fill-opacity=\"0.2\"/>"
+        ));
+        assert!(image.contains(
+            "<rect x=\"65\" y=\"30\" width=\"10\" height=\"35\" rx=\"5\" fill=\"red\"/>"
+        ));
+    }
+
+    #[test]
+    fn svg_escapes_color() {
+        let options = SvgOptions::new(Board::Small).color("red\"/><script>'&");
+        let image = svg(Frame::new().display_buffer(), &options.ghosting(1.));
+        assert!(image.contains("fill=\"red&quot;/&gt;&lt;script&gt;&apos;&amp;\""));
+        assert!(!image.contains("<script>"));
+    }
+
+    #[test]
+    fn svg_ghosting_and_board() {
+        let frame = Frame::new();
+
+        let options = SvgOptions::new(Board::Small)
+            .ghosting(0.2)
+            .digit_height(100.);
+        let image = svg(frame.display_buffer(), &options);
+        // All 7 segments and the dot of each digit, and the colon.
+        assert_eq!(image.matches("fill-opacity=\"0.2\"").count(), 4 * 8 + 2);
+        // The digit and a margin above and below it.
+        assert!(image.contains("height=\"140\""));
+
+        let options = SvgOptions::new(Board::Large)
+            .ghosting(0.2)
+            .digit_height(200.);
+        let image = svg(frame.display_buffer(), &options);
+        assert_eq!(image.matches("fill-opacity=\"0.2\"").count(), 4 * 8 + 5);
+        assert!(image.contains("height=\"280\""));
+    }
+}
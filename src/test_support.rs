@@ -0,0 +1,38 @@
+// Copyright (c) 2020 Karl Thorén <karl.h.thoren@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Helpers shared by the unit tests.
+
+use core::cell::RefCell;
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+/// A driver's handle to a shared bus, like the proxies of `shared-bus`.
+/// Also lets a test look at the bus while a driver uses it.
+pub(crate) struct SharedBus<'a, I2C>(pub(crate) &'a RefCell<I2C>);
+
+impl<I2C: Write> Write for SharedBus<'_, I2C> {
+    type Error = I2C::Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.0.borrow_mut().write(address, bytes)
+    }
+}
+
+impl<I2C: WriteRead> WriteRead for SharedBus<'_, I2C> {
+    type Error = I2C::Error;
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.0.borrow_mut().write_read(address, bytes, buffer)
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright (c) 2020 Karl Thorén <karl.h.thoren@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Stopwatch and countdown timer widget.
+
+use crate::{Index, SevenSegment};
+
+/// Whether a timer is still running.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimerStatus {
+    /// The stopwatch is counting, or the countdown has time left.
+    Running,
+    /// The countdown has reached zero.
+    Expired,
+}
+
+/// A stopwatch or countdown shown on the whole display.
+///
+/// The timer is driven by the elapsed milliseconds, it keeps no clock of its own.
+/// The shown time switches presentation as it grows:
+/// * Below a minute as seconds and hundredths, `SS.hh`.
+/// * Below an hour as minutes and seconds, `MM:SS`.
+/// * Otherwise as hours and minutes, `HH:MM`, up to `99:59`.
+///
+/// The colon blinks, it is on during the first half of every second.
+/// A countdown rounds the time left up, so it shows zero only once it has expired.
+///
+/// # Examples
+///
+/// ```
+/// use adafruit_7segment::{Frame, Timer, TimerStatus};
+///
+/// let mut frame = Frame::new();
+///
+/// // A three minute countdown, 10 seconds in. Display reads "2:50".
+/// let timer = Timer::countdown(3 * 60 * 1000);
+/// assert_eq!(timer.update(&mut frame, 10_000), TimerStatus::Running);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timer {
+    // `None` for a stopwatch.
+    duration_ms: Option<u32>,
+}
+
+/// Milliseconds in a minute and in an hour.
+const MINUTE_MS: u32 = 60 * 1000;
+const HOUR_MS: u32 = 60 * MINUTE_MS;
+
+impl Timer {
+    /// Create a stopwatch, showing the elapsed time.
+    pub fn stopwatch() -> Self {
+        Timer { duration_ms: None }
+    }
+
+    /// Create a countdown, showing the time left of `duration_ms` milliseconds.
+    pub fn countdown(duration_ms: u32) -> Self {
+        Timer {
+            duration_ms: Some(duration_ms),
+        }
+    }
+
+    /// Whether the countdown has reached zero after `elapsed_ms` milliseconds.
+    /// A stopwatch never expires.
+    pub fn is_expired(&self, elapsed_ms: u32) -> bool {
+        match self.duration_ms {
+            Some(duration_ms) => elapsed_ms >= duration_ms,
+            None => false,
+        }
+    }
+
+    /// Update the buffer with the time after `elapsed_ms` milliseconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `display` - The display to draw on, all digits and the colon are updated.
+    /// * `elapsed_ms` - Milliseconds since the timer was started.
+    pub fn update<S, E>(&self, display: &mut S, elapsed_ms: u32) -> TimerStatus
+    where
+        S: SevenSegment<E> + ?Sized,
+    {
+        let (shown_ms, round_up) = match self.duration_ms {
+            Some(duration_ms) => (duration_ms.saturating_sub(elapsed_ms), true),
+            None => (elapsed_ms, false),
+        };
+        let colon_on = elapsed_ms % 1000 < 500;
+
+        // The presentation is picked after rounding, so 59.999 s left shows 1:00, not 60.00.
+        let hundredths = divide(shown_ms, 10, round_up);
+        let seconds = divide(shown_ms, 1000, round_up);
+        if hundredths < MINUTE_MS / 10 {
+            show_pair(display, hundredths / 100, hundredths % 100, true, false);
+        } else if seconds < HOUR_MS / 1000 {
+            show_pair(display, seconds / 60, seconds % 60, false, colon_on);
+        } else {
+            let minutes = divide(shown_ms, MINUTE_MS, round_up).min(99 * 60 + 59);
+            show_pair(display, minutes / 60, minutes % 60, false, colon_on);
+        }
+
+        if self.is_expired(elapsed_ms) {
+            TimerStatus::Expired
+        } else {
+            TimerStatus::Running
+        }
+    }
+}
+
+/// Divide, rounding up or down.
+fn divide(value: u32, divisor: u32, round_up: bool) -> u32 {
+    if round_up {
+        let quotient = value / divisor;
+        if quotient * divisor == value {
+            quotient
+        } else {
+            quotient + 1
+        }
+    } else {
+        value / divisor
+    }
+}
+
+/// Show two numbers of two digits each, the first without leading zero,
+/// separated by a dot or the colon.
+fn show_pair<S, E>(display: &mut S, high: u32, low: u32, dot: bool, colon: bool)
+where
+    S: SevenSegment<E> + ?Sized,
+{
+    let digits = [high / 10 % 10, high % 10, low / 10, low % 10];
+    for (i, &digit) in digits.iter().enumerate() {
+        let index = Index::from(i as u8);
+        if i == 0 && digit == 0 {
+            display.update_buffer_with_segments(index, 0);
+        } else {
+            display.update_buffer_with_digit(index, digit as u8);
+        }
+    }
+    display.update_buffer_with_dot(Index::Two, dot);
+    display.update_buffer_with_colon(colon);
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use self::std::string::{String, ToString};
+    use super::*;
+    use crate::{DecodedBuffer, Frame};
+
+    fn shown(timer: &Timer, elapsed_ms: u32) -> (String, TimerStatus) {
+        let mut frame = Frame::new();
+        let status = timer.update(&mut frame, elapsed_ms);
+        (
+            DecodedBuffer::new(frame.display_buffer()).to_string(),
+            status,
+        )
+    }
+
+    #[test]
+    fn stopwatch() {
+        let timer = Timer::stopwatch();
+        assert_eq!(shown(&timer, 5_329).0, " 5.32");
+        assert_eq!(shown(&timer, 59_999).0, "59.99");
+        assert_eq!(shown(&timer, 65_000).0, " 1:05");
+        assert_eq!(shown(&timer, 65_600).0, " 105");
+        assert_eq!(shown(&timer, 3_725_000).0, " 1:02");
+        assert_eq!(shown(&timer, u32::MAX).0, "99:59");
+        assert!(!timer.is_expired(u32::MAX));
+    }
+
+    #[test]
+    fn countdown() {
+        let timer = Timer::countdown(10_000);
+        assert_eq!(
+            shown(&timer, 2_500),
+            (String::from(" 7.50"), TimerStatus::Running)
+        );
+        assert_eq!(
+            shown(&timer, 9_999),
+            (String::from(" 0.01"), TimerStatus::Running)
+        );
+        assert_eq!(
+            shown(&timer, 12_000),
+            (String::from(" 0.00"), TimerStatus::Expired)
+        );
+
+        let timer = Timer::countdown(2 * HOUR_MS);
+        assert_eq!(shown(&timer, 1).0, " 2:00");
+        assert_eq!(shown(&timer, HOUR_MS + 1_000).0, "59:59");
+        assert_eq!(shown(&timer, HOUR_MS + 1_500).0, "5959");
+
+        // Rounding up past a switch of presentation shows the next one.
+        assert_eq!(shown(&timer, 2 * HOUR_MS - 59_999).0, " 1:00");
+        assert_eq!(shown(&timer, 2 * HOUR_MS - 59_990).0, "59.99");
+        assert_eq!(shown(&timer, 2 * HOUR_MS - 60_999).0, " 1:01");
+        assert_eq!(shown(&timer, HOUR_MS + 1).0, " 1:00");
+        assert_eq!(shown(&timer, HOUR_MS).0, " 1:00");
+        assert_eq!(shown(&timer, HOUR_MS + 1_000).0, "59:59");
+    }
+}
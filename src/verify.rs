@@ -0,0 +1,218 @@
+// Copyright (c) 2020 Karl Thorén <karl.h.thoren@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Reading back the display RAM to detect and recover from a reset of the display.
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use ht16k33::{DisplayData, DisplayDataAddress, LedLocation, Oscillator, HT16K33, ROWS_SIZE};
+
+use crate::Error;
+
+/// Outcome of a verification of the display RAM.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verification {
+    /// The display RAM matches the buffer.
+    Match,
+    /// The display RAM differs from the buffer, recovery was not requested.
+    Mismatch,
+    /// The display RAM differed, the display was initialized again and the buffer re-sent.
+    Recovered,
+    /// The display RAM still differs after initializing the display again and re-sending the buffer.
+    Unrecovered,
+}
+
+/// Trait for checking that the display still shows the buffer, for example after a brownout.
+pub trait Verify<E> {
+    /// Read back the display RAM and compare it with the buffer, optionally recovering on mismatch.
+    fn verify(&mut self, recover: bool) -> Result<Verification, Error>;
+}
+
+/// Put the rows back into the buffer of the display.
+fn restore_buffer<I2C, E>(display: &mut HT16K33<I2C>, buffer: &[DisplayData; ROWS_SIZE])
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    for (row, value) in buffer.iter().enumerate() {
+        for bit in 0..8 {
+            let common = DisplayData::from_bits_truncate(1 << bit);
+            let location = LedLocation {
+                row: DisplayDataAddress::from_bits_truncate(row as u8),
+                common,
+            };
+            display.update_display_buffer(location, value.contains(common));
+        }
+    }
+}
+
+/// Read the display RAM, leaving the buffer untouched.
+fn ram_matches<I2C, E>(display: &mut HT16K33<I2C>) -> Result<bool, Error>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    let intended = *display.display_buffer();
+    let read = display.read_display_buffer();
+    let matches = *display.display_buffer() == intended;
+    restore_buffer(display, &intended);
+    read.map_err(|_| Error::Bus)?;
+    Ok(matches)
+}
+
+impl<I2C, E> Verify<E> for HT16K33<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Read back the display RAM and compare it with the buffer.
+    ///
+    /// A display that lost power while the MCU kept running comes back with the
+    /// oscillator and display off and arbitrary RAM. With `recover` set, a mismatch
+    /// initializes the display again, restores the oscillator, display and dimming
+    /// settings, re-sends the buffer and reads the RAM back once more.
+    /// The buffer is left untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `recover` - Initialize the display again and re-send the buffer on mismatch.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ht16k33::i2c_mock::I2cMock;
+    /// use ht16k33::HT16K33;
+    /// use adafruit_7segment::{Verification, Verify};
+    ///
+    /// // Create an I2C device.
+    /// let mut i2c = I2cMock::new();
+    ///
+    /// // The I2C device address.
+    /// const DISP_I2C_ADDR: u8 = 112;
+    ///
+    /// let mut ht16k33 = HT16K33::new(i2c, DISP_I2C_ADDR);
+    /// ht16k33.initialize().expect("Failed to initialize ht16k33");
+    ///
+    /// match ht16k33.verify(true).expect("Could not read the display RAM!") {
+    ///     Verification::Match | Verification::Recovered => {}
+    ///     _ => panic!("The display does not show the buffer!"),
+    /// }
+    /// ```
+    fn verify(&mut self, recover: bool) -> Result<Verification, Error> {
+        if ram_matches(self)? {
+            return Ok(Verification::Match);
+        }
+        if !recover {
+            return Ok(Verification::Mismatch);
+        }
+
+        let intended = *self.display_buffer();
+        let oscillator = *self.oscillator();
+        let display = *self.display();
+        let dimming = *self.dimming();
+
+        // Initializing turns the oscillator on and clears the buffer.
+        self.initialize().map_err(|_| Error::Bus)?;
+        restore_buffer(self, &intended);
+        if oscillator != Oscillator::ON {
+            self.set_oscillator(oscillator).map_err(|_| Error::Bus)?;
+        }
+        self.set_dimming(dimming).map_err(|_| Error::Bus)?;
+        self.set_display(display).map_err(|_| Error::Bus)?;
+        self.write_display_buffer().map_err(|_| Error::Bus)?;
+
+        if ram_matches(self)? {
+            Ok(Verification::Recovered)
+        } else {
+            Ok(Verification::Unrecovered)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use self::std::vec;
+    use self::std::vec::Vec;
+    use super::*;
+    use crate::{Index, SevenSegment};
+    use embedded_hal_mock::i2c::{Mock as I2cMock, Transaction};
+    use ht16k33::Display;
+
+    const ADDRESS: u8 = 0;
+
+    /// The RAM image with the first digit showing 1.
+    fn ram() -> Vec<u8> {
+        let mut ram = vec![0; ROWS_SIZE];
+        ram[0] = 0b0000_0110;
+        ram
+    }
+
+    fn read(ram: Vec<u8>) -> Transaction {
+        Transaction::write_read(ADDRESS, vec![0x00], ram)
+    }
+
+    #[test]
+    fn verify_match_and_mismatch() {
+        let expectations = [read(ram()), read(vec![0xFF; ROWS_SIZE])];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut ht16k33 = HT16K33::new(i2c, ADDRESS);
+        ht16k33.update_buffer_with_digit(Index::One, 1);
+
+        assert_eq!(ht16k33.verify(false).unwrap(), Verification::Match);
+        assert_eq!(ht16k33.verify(false).unwrap(), Verification::Mismatch);
+        // The buffer is not replaced by what was read.
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0000_0110);
+        assert_eq!(ht16k33.display_buffer()[1].bits(), 0b0000_0000);
+
+        i2c = ht16k33.destroy();
+        i2c.done();
+    }
+
+    #[test]
+    fn verify_recover() {
+        let mut write_buffer = vec![0x00];
+        write_buffer.extend(ram());
+        let mut cleared = vec![0x00];
+        cleared.extend([0; ROWS_SIZE].iter().cloned());
+        let expectations = [
+            Transaction::write(ADDRESS, vec![0x21]),
+            Transaction::write(ADDRESS, vec![0x81]),
+            Transaction::write(ADDRESS, vec![0xE7]),
+            // Blank after a brownout.
+            read(vec![0; ROWS_SIZE]),
+            // Initialize
+            Transaction::write(ADDRESS, vec![0x21]),
+            Transaction::write(ADDRESS, vec![0x80]),
+            Transaction::write(ADDRESS, vec![0xEF]),
+            Transaction::write(ADDRESS, cleared),
+            // Restore the settings and the buffer.
+            Transaction::write(ADDRESS, vec![0xE7]),
+            Transaction::write(ADDRESS, vec![0x81]),
+            Transaction::write(ADDRESS, write_buffer),
+            read(ram()),
+            // Still not working.
+            read(vec![0; ROWS_SIZE]),
+        ];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut ht16k33 = HT16K33::new(i2c, ADDRESS);
+        ht16k33.set_oscillator(Oscillator::ON).unwrap();
+        ht16k33.set_display(Display::ON).unwrap();
+        ht16k33
+            .set_dimming(ht16k33::Dimming::from_u8(7).unwrap())
+            .unwrap();
+        ht16k33.update_buffer_with_digit(Index::One, 1);
+
+        assert_eq!(ht16k33.verify(true).unwrap(), Verification::Recovered);
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0000_0110);
+        assert_eq!(*ht16k33.display(), Display::ON);
+
+        assert!(matches!(ht16k33.verify(false), Ok(Verification::Mismatch)));
+
+        i2c = ht16k33.destroy();
+        i2c.done();
+    }
+}
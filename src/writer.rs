@@ -0,0 +1,282 @@
+// Copyright (c) 2020 Karl Thorén <karl.h.thoren@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `core::fmt::Write` adapter for the 7-segment display.
+//!
+//! With the `ufmt` feature the adapter also implements `ufmt::uWrite`.
+
+use core::fmt;
+use core::marker::PhantomData;
+
+use crate::{AsciiChar, Error, Index, SevenSegment};
+
+/// Number of digits on the display.
+const DIGITS: u8 = 4;
+
+/// Writer placing formatted text on the display, one digit per character.
+///
+/// Creating the writer starts a new frame: all digits, dots and the colon are blanked
+/// and the cursor is placed at the first digit.
+/// A `.` is folded into the dot of the previous digit and a `:` turns on the colon,
+/// neither of them uses up a digit. Characters that do not fit on the display are
+/// dropped and the write returns an error.
+///
+/// # Examples
+///
+/// ```
+/// use core::fmt::Write;
+/// use ht16k33::i2c_mock::I2cMock;
+/// use ht16k33::HT16K33;
+/// use adafruit_7segment::DisplayWriter;
+///
+/// // Create an I2C device.
+/// let mut i2c = I2cMock::new();
+///
+/// // The I2C device address.
+/// const DISP_I2C_ADDR: u8 = 112;
+///
+/// let mut ht16k33 = HT16K33::new(i2c, DISP_I2C_ADDR);
+///
+/// // Display reads "23.5C".
+/// let mut writer = DisplayWriter::new(&mut ht16k33);
+/// write!(writer, "{:.1}C", 23.54).expect("Text does not fit on the display!");
+/// ```
+pub struct DisplayWriter<'a, S: ?Sized, E> {
+    display: &'a mut S,
+    cursor: u8,
+    dot_free: bool,
+    _error: PhantomData<E>,
+}
+
+impl<'a, S, E> DisplayWriter<'a, S, E>
+where
+    S: SevenSegment<E> + ?Sized,
+{
+    /// Create a writer and start a new frame on the display.
+    pub fn new(display: &'a mut S) -> Self {
+        let mut writer = DisplayWriter {
+            display,
+            cursor: 0,
+            dot_free: false,
+            _error: PhantomData,
+        };
+        writer.new_frame();
+        writer
+    }
+
+    /// Blank the display and move the cursor back to the first digit.
+    pub fn new_frame(&mut self) {
+        for i in 0..DIGITS {
+            let index = Index::from(i);
+            // A space is always valid.
+            let _ = self
+                .display
+                .update_buffer_with_char(index, AsciiChar::Space);
+            self.display.update_buffer_with_dot(index, false);
+        }
+        self.display.update_buffer_with_colon(false);
+        self.cursor = 0;
+        self.dot_free = false;
+    }
+
+    /// Put a single character at the cursor.
+    pub(crate) fn put(&mut self, c: char) -> Result<(), Error> {
+        match c {
+            ':' => self.display.update_buffer_with_colon(true),
+            '.' if self.dot_free => {
+                let index = Index::from(self.cursor - 1);
+                self.display.update_buffer_with_dot(index, true);
+                self.dot_free = false;
+            }
+            _ => {
+                if self.cursor >= DIGITS {
+                    return Err(Error::InsufficientDigits);
+                }
+                let index = Index::from(self.cursor);
+                if c == '.' {
+                    // No digit to attach the dot to, show it on a blank digit.
+                    self.display
+                        .update_buffer_with_char(index, AsciiChar::Space)?;
+                    self.display.update_buffer_with_dot(index, true);
+                    self.dot_free = false;
+                } else {
+                    let value = AsciiChar::from_ascii(c).map_err(|_| Error::NotValidChar)?;
+                    self.display.update_buffer_with_char(index, value)?;
+                    self.dot_free = true;
+                }
+                self.cursor += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a, S, E> fmt::Write for DisplayWriter<'a, S, E>
+where
+    S: SevenSegment<E> + ?Sized,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            self.put(c).map_err(|_| fmt::Error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writing with `uwrite!` for targets where `core::fmt` is too large.
+///
+/// # Examples
+///
+/// ```
+/// use ht16k33::i2c_mock::I2cMock;
+/// use ht16k33::HT16K33;
+/// use adafruit_7segment::DisplayWriter;
+/// use ufmt::uwrite;
+///
+/// // Create an I2C device.
+/// let mut i2c = I2cMock::new();
+///
+/// // The I2C device address.
+/// const DISP_I2C_ADDR: u8 = 112;
+///
+/// let mut ht16k33 = HT16K33::new(i2c, DISP_I2C_ADDR);
+///
+/// // Display reads "-42".
+/// let mut writer = DisplayWriter::new(&mut ht16k33);
+/// uwrite!(writer, "{}", -42i16).expect("Text does not fit on the display!");
+/// ```
+#[cfg(feature = "ufmt")]
+impl<'a, S, E> ufmt::uWrite for DisplayWriter<'a, S, E>
+where
+    S: SevenSegment<E> + ?Sized,
+{
+    type Error = Error;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Error> {
+        for c in s.chars() {
+            self.put(c)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use core::fmt::Write;
+    use embedded_hal_mock as hal;
+
+    use self::hal::i2c::Mock as I2cMock;
+    use super::*;
+    use ht16k33::HT16K33;
+
+    const ADDRESS: u8 = 0;
+
+    #[test]
+    fn write_folds_dot_and_colon() {
+        let expectations = [];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut ht16k33 = HT16K33::new(i2c, ADDRESS);
+
+        let mut writer = DisplayWriter::new(&mut ht16k33);
+        assert!(write!(writer, "{:.1}C", 23.54).is_ok());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0101_1011);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b1100_1111);
+        assert_eq!(ht16k33.display_buffer()[4].bits(), 0b0000_0000);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b0110_1101);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0011_1001);
+
+        let mut writer = DisplayWriter::new(&mut ht16k33);
+        assert!(write!(writer, "12:{:02}", 5).is_ok());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0000_0110);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b0101_1011);
+        assert_eq!(ht16k33.display_buffer()[4].bits(), 0b0000_0010);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b0011_1111);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0110_1101);
+
+        i2c = ht16k33.destroy();
+        i2c.done();
+    }
+
+    #[test]
+    fn write_leading_dot() {
+        let expectations = [];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut ht16k33 = HT16K33::new(i2c, ADDRESS);
+
+        let mut writer = DisplayWriter::new(&mut ht16k33);
+        assert!(write!(writer, ".5..").is_ok());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b1000_0000);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b1110_1101);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b1000_0000);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0000_0000);
+
+        i2c = ht16k33.destroy();
+        i2c.done();
+    }
+
+    #[test]
+    fn write_overflow_and_invalid() {
+        let expectations = [];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut ht16k33 = HT16K33::new(i2c, ADDRESS);
+
+        // Overflowing text is clipped.
+        let mut writer = DisplayWriter::new(&mut ht16k33);
+        assert!(write!(writer, "12345").is_err());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0000_0110);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0110_0110);
+
+        // A trailing dot still fits on the last digit.
+        let mut writer = DisplayWriter::new(&mut ht16k33);
+        assert!(write!(writer, "1234.").is_ok());
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b1110_0110);
+
+        let mut writer = DisplayWriter::new(&mut ht16k33);
+        assert!(write!(writer, "1_").is_err());
+        assert!(write!(writer, "1°").is_err());
+
+        // Starting a new frame blanks the display.
+        writer.new_frame();
+        for row in ht16k33.display_buffer().iter() {
+            assert_eq!(row.bits(), 0b0000_0000);
+        }
+
+        i2c = ht16k33.destroy();
+        i2c.done();
+    }
+
+    #[cfg(feature = "ufmt")]
+    #[test]
+    fn uwrite() {
+        let expectations = [];
+
+        let mut i2c = I2cMock::new(&expectations);
+        let mut ht16k33 = HT16K33::new(i2c, ADDRESS);
+
+        let mut writer = DisplayWriter::new(&mut ht16k33);
+        assert!(ufmt::uwrite!(writer, "{}:{}", 1u8, -5i32).is_ok());
+        assert_eq!(ht16k33.display_buffer()[0].bits(), 0b0000_0110);
+        assert_eq!(ht16k33.display_buffer()[2].bits(), 0b0100_0000);
+        assert_eq!(ht16k33.display_buffer()[4].bits(), 0b0000_0010);
+        assert_eq!(ht16k33.display_buffer()[6].bits(), 0b0110_1101);
+        assert_eq!(ht16k33.display_buffer()[8].bits(), 0b0000_0000);
+
+        let mut writer = DisplayWriter::new(&mut ht16k33);
+        assert!(matches!(
+            ufmt::uwrite!(writer, "{}", 12345u32),
+            Err(Error::InsufficientDigits)
+        ));
+
+        i2c = ht16k33.destroy();
+        i2c.done();
+    }
+}